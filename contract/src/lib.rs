@@ -1,5 +1,7 @@
+use near_sdk::json_types::{Base64VecU8, U128};
 use near_sdk::{
-    env, near, require, AccountId, NearToken, PanicOnDefault, Promise,
+    env, near, require, AccountId, CurveType, Gas, NearToken, PanicOnDefault, Promise,
+    PromiseOrValue, PublicKey,
 };
 
 const YOCTO_PER_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
@@ -21,6 +23,24 @@ const CREATE_RECORD_PRESSURE_YOCTO: u128 = 16_000_000_000_000_000_000_000; // 0.
 
 const MIN_ELIGIBLE_POSITION_YOCTO: u128 = YOCTO_PER_NEAR; // 1 NEAR anti-spam floor
 
+const TPI_MAX_VENUE_LEN: usize = 64;
+const TPI_MAX_MEMO_LEN: usize = 256;
+
+const AUDIT_MAX_AUDITOR_LEN: usize = 128;
+const AUDIT_MAX_SCOPE_LEN: usize = 256;
+const SHA256_HEX_LEN: usize = 64;
+const GIT_COMMIT_HEX_LEN: usize = 40;
+const EXPLORER_URL_MAX_LEN: usize = 256;
+
+const DEFAULT_BETA_MAX_POSITION_YOCTO: u128 = 100 * YOCTO_PER_NEAR; // per allowlisted account
+const DEFAULT_BETA_TVL_CAP_YOCTO: u128 = 1_000 * YOCTO_PER_NEAR; // all locked positions
+
+const TPI_TREND_WINDOW: usize = 5; // last N closed deployments
+const TPI_FULL_MIN_EFFICIENCY_BPS: u128 = 10_000; // break-even or better
+const TPI_REDUCED_MIN_EFFICIENCY_BPS: u128 = 9_000; // up to 10% average loss
+const TPI_FULL_MAX_DEPLOY_BPS: u128 = 2_000; // 20% of the treasury bucket
+const TPI_REDUCED_MAX_DEPLOY_BPS: u128 = 500; // 5% of the treasury bucket
+
 const SECONDS_TO_NANOS: u64 = 1_000_000_000;
 const PRODUCTION_LOCK_NS: u64 = 63_072_000 * SECONDS_TO_NANOS; // 2 years using 365-day years
 const PRODUCTION_EXIT_WINDOW_NS: u64 = 604_800 * SECONDS_TO_NANOS; // 7 days
@@ -28,6 +48,36 @@ const PRODUCTION_EXIT_WINDOW_NS: u64 = 604_800 * SECONDS_TO_NANOS; // 7 days
 const TEST_LOCK_NS: u64 = 7_200 * SECONDS_TO_NANOS; // 2 hours
 const TEST_EXIT_WINDOW_NS: u64 = 420 * SECONDS_TO_NANOS; // 7 minutes
 
+const PRODUCTION_MIN_HOLDING_AGE_NS: u64 = 604_800 * SECONDS_TO_NANOS; // 7 days
+const TEST_MIN_HOLDING_AGE_NS: u64 = 600 * SECONDS_TO_NANOS; // 10 minutes
+const PRODUCTION_CLAIM_WINDOW_NS: u64 = 2_592_000 * SECONDS_TO_NANOS; // 30 days
+const TEST_CLAIM_WINDOW_NS: u64 = 1_800 * SECONDS_TO_NANOS; // 30 minutes
+const PRODUCTION_MIN_INACTIVITY_NS: u64 = 15_768_000 * SECONDS_TO_NANOS; // 182.5 days
+const TEST_MIN_INACTIVITY_NS: u64 = 1_800 * SECONDS_TO_NANOS; // 30 minutes
+const PRODUCTION_INHERITANCE_CHALLENGE_NS: u64 = 2_592_000 * SECONDS_TO_NANOS; // 30 days
+const TEST_INHERITANCE_CHALLENGE_NS: u64 = 300 * SECONDS_TO_NANOS; // 5 minutes
+
+const MAX_EXIT_SCHEDULE_COUNT: u64 = 32;
+
+const EARLY_EXIT_MAX_PENALTY_BPS: u128 = 2_000; // 20% at lock start, decaying to 0 at window open
+
+const ERUPTION_INLINE_WORK_BUDGET: u64 = 50; // participants scanned inside user calls
+const ERUPTION_CRANK_WORK_BUDGET: u64 = 400;
+
+const MAX_CLAIM_AGENTS: usize = 8;
+
+const MIN_SCHEDULED_WITHDRAWAL_YOCTO: u128 = YOCTO_PER_NEAR;
+const MAX_SCHEDULED_WITHDRAWALS_PER_ACCOUNT: usize = 4;
+const CLAIM_ALL_CALLBACK_GAS: Gas = Gas::from_tgas(30);
+
+const CRANK_MAX_STEPS: u64 = 8;
+const CRANK_BOUNTY_PER_STEP_YOCTO: u128 = 1_000_000_000_000_000_000_000; // 0.001 NEAR
+const CRANK_MAX_BOUNTY_YOCTO: u128 = 5_000_000_000_000_000_000_000; // 0.005 NEAR per call
+
+const PRODUCTION_GOVERNANCE_TIMELOCK_NS: u64 = 1_209_600 * SECONDS_TO_NANOS; // 14 days
+const TEST_GOVERNANCE_TIMELOCK_NS: u64 = 600 * SECONDS_TO_NANOS; // 10 minutes
+
+// Genesis default; the live table is `Contract::phase_milestones`.
 const PHASE_MILESTONES_YOCTO: [u128; 16] = [
     100_000 * YOCTO_PER_NEAR,
     250_000 * YOCTO_PER_NEAR,
@@ -57,6 +107,205 @@ pub struct Participant {
     pub created_at: u64,
     pub lock_started_at: u64,
     pub claimed_eruption_ids: Vec<u64>,
+    pub lots: Vec<PositionLot>,
+    pub next_lot_id: u64,
+    pub auto_compound: bool,
+    pub claim_agents: Vec<AccountId>,
+    pub beneficiary_id: Option<AccountId>,
+    pub inactivity_period_ns: u64,
+    pub last_active_at: u64,
+    pub inheritance_claim: Option<InheritanceClaim>,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct InheritanceClaim {
+    pub beneficiary_id: AccountId,
+    pub started_at: u64,
+    pub executable_at: u64,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct PositionLot {
+    pub lot_id: u64,
+    pub amount: u128,
+    pub original_amount: u128,
+    pub lock_started_at: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct PositionLotView {
+    pub lot_id: u64,
+    pub amount: U128,
+    pub original_amount: U128,
+    pub lock_started_at: u64,
+    pub exit_window_open: bool,
+    pub next_exit_window_start: u64,
+    pub next_exit_window_end: u64,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct ScheduledWithdrawal {
+    pub request_id: u64,
+    pub account_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: u128,
+    pub created_at: u64,
+    // Paid by the participant when scheduling, refunded on removal.
+    pub storage_deposit: u128,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct PendingEruption {
+    pub phase_number: u64,
+    pub threshold: u128,
+    pub as_of: u64,
+    pub participant_count: u64,
+    pub cursor: u64,
+    pub eligible_accounts: Vec<AccountId>,
+    pub eligible_positions: Vec<u128>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct ClaimedShare {
+    pub eruption_id: u64,
+    pub share: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct ClaimableEruption {
+    pub eruption_id: u64,
+    pub share: U128,
+    pub claim_deadline: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EruptionProjection {
+    pub phase_number: u64,
+    pub threshold: Option<U128>, // None past the end of the phase rule
+    pub pressure: U128,
+    pub shortfall: U128,
+    pub distribution_pool: U128,
+    pub retained: U128,
+    pub distribution_mode: DistributionMode,
+    pub eligible_count: u64,
+    pub total_eligible_position: U128,
+    pub share_per_wallet: U128, // zero in position-weighted mode
+    pub unallocated_remainder: U128,
+    pub scan_in_progress: bool,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct DepositImpactQuote {
+    pub account_id: Option<AccountId>,
+    pub amount: U128,
+    pub fee: U128,
+    pub locked_position: U128,
+    pub pressure_added: U128,
+    pub pressure_before: U128,
+    pub pressure_after: U128,
+    pub next_threshold: Option<U128>,
+    pub shortfall_after: U128,
+    pub triggers_eruption: bool,
+    pub eruptions_triggered: u64,
+    pub pressure_after_eruptions: U128,
+    pub deposit_allowed: bool,
+    pub rejection_reason: Option<String>,
+    pub max_locked_position_addable: Option<U128>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct InheritanceStatus {
+    pub account_id: AccountId,
+    pub beneficiary_id: Option<AccountId>,
+    pub inactivity_period_ns: u64,
+    pub last_active_at: u64,
+    pub inactive_from: Option<u64>,
+    pub challenge_ns: u64,
+    pub inheritance_claim: Option<InheritanceClaim>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct Claimable {
+    pub account_id: AccountId,
+    pub eruptions: Vec<ClaimableEruption>,
+    pub total: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct CrankResult {
+    pub sweeps: u64,
+    pub withdrawals: u64,
+    pub eruptions: u64,
+    pub bounty: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct CrankWork {
+    pub sweepable_eruptions: u64,
+    pub actionable_withdrawals: u64,
+    pub eruption_pending: bool,
+    pub keeper_budget: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct ExitWindow {
+    pub start: u64,
+    pub end: u64,
+    pub lot_ids: Vec<u64>,
+    pub amount: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct ExitSchedule {
+    pub account_id: AccountId,
+    pub now: u64,
+    pub exit_window_open: bool,
+    pub ns_until_next_opening: Option<u64>,
+    pub ns_until_current_window_closes: Option<u64>,
+    pub windows: Vec<ExitWindow>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EarlyExitLot {
+    pub lot_id: u64,
+    pub amount: U128,
+    pub ns_until_window: u64,
+    pub penalty_bps: u128,
+    pub penalty: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EarlyExitQuote {
+    pub account_id: AccountId,
+    pub gross: U128,
+    pub fee: U128,
+    pub penalty: U128,
+    pub net_to_user: U128,
+    pub lots: Vec<EarlyExitLot>,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct LotConsumption {
+    pub lot_id: u64,
+    pub amount: U128,
 }
 
 #[near(serializers = [borsh, json])]
@@ -74,6 +323,109 @@ pub struct EruptionSnapshot {
     pub created_at: u64,
     pub claim_deadline: u64,
     pub eligible_accounts: Vec<AccountId>,
+    pub swept: bool,
+    pub distribution_mode: DistributionMode,
+    pub eligible_shares: Vec<u128>, // parallel to eligible_accounts; empty in equal-share mode
+    pub claimed_amount: u128,
+    pub claim_window_ns: u64,
+}
+
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DistributionMode {
+    #[default]
+    EqualShare,
+    PositionWeighted,
+}
+
+#[near(serializers = [json])]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClaimApplication {
+    Transferred,
+    Compounded,
+}
+
+// Geometric continuation after the last milestone: each further phase is the
+// previous threshold times `ratio_bps / 10_000`, for at most `max_phases`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PhaseContinuation {
+    pub ratio_bps: u128,
+    pub max_phases: Option<u64>,
+}
+
+// Genesis phase table; `None` at init keeps the default 16 milestones with no
+// continuation.
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct PhaseTableConfig {
+    pub milestones: Vec<u128>,
+    pub continuation: Option<PhaseContinuation>,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct PhaseMilestoneProposal {
+    pub milestones: Vec<u128>,
+    pub proposed_at: u64,
+    pub executable_at: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct PhaseRule {
+    pub milestones: Vec<u128>,
+    pub continuation: Option<PhaseContinuation>,
+    pub governance_timelock_ns: u64,
+    pub pending_proposal: Option<PhaseMilestoneProposal>,
+}
+
+impl EruptionSnapshot {
+    pub fn share_of(&self, account_id: &AccountId) -> u128 {
+        match self.eligible_accounts.iter().position(|a| a == account_id) {
+            Some(index) if self.distribution_mode == DistributionMode::PositionWeighted => {
+                self.eligible_shares[index]
+            }
+            Some(_) => self.share_per_wallet,
+            None => 0,
+        }
+    }
+}
+
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatusCode {
+    Development,
+    Audited,
+    Public,
+    Pending,
+    Verified,
+    Paused,
+    Specified,
+    Implemented,
+    Exhausted,
+    Full,
+    Reduced,
+    Frozen,
+    NotApproved,
+    Approved,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct SystemStatusCodes {
+    pub project_status: StatusCode,
+    pub website_status: StatusCode,
+    pub wallet_status: StatusCode,
+    pub contract_status: StatusCode,
+    pub treasury_status: StatusCode,
+    pub eruption_engine_status: StatusCode,
+    pub oim_status: StatusCode,
+    pub tpi_status: StatusCode,
+    pub public_fund_launch_status: StatusCode,
 }
 
 #[near(serializers = [borsh, json])]
@@ -88,147 +440,886 @@ pub struct SystemStatus {
     pub oim_status: String,
     pub tpi_status: String,
     pub public_fund_launch_status: String,
+    pub codes: SystemStatusCodes,
 }
 
-#[near(contract_state)]
-#[derive(PanicOnDefault)]
-pub struct Contract {
-    pub owner_id: AccountId,
-
-    pub operations_wallet: AccountId,
-    pub treasury_wallet: AccountId,
-    pub growth_wallet: AccountId,
-    pub reserve_wallet: AccountId,
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LaunchStage {
+    Development,
+    Audited,
+    Public,
+}
 
-    pub test_mode: bool,
-    pub lock_duration_ns: u64,
-    pub exit_window_ns: u64,
+impl LaunchStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LaunchStage::Development => "DEVELOPMENT",
+            LaunchStage::Audited => "AUDITED",
+            LaunchStage::Public => "PUBLIC",
+        }
+    }
+}
 
-    pub participants: Vec<Participant>,
-    pub eruptions: Vec<EruptionSnapshot>,
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Default)]
+pub struct LaunchArtifacts {
+    pub code_hash: Option<String>,
+    pub commit_hash: Option<String>,
+    pub audit_report_hash: Option<String>,
+    pub audited_at: Option<u64>,
+    pub contract_account_id: Option<AccountId>,
+    pub explorer_url: Option<String>,
+    pub public_at: Option<u64>,
+}
 
-    pub volcano_pressure: u128,
-    pub eruption_count: u64,
-    pub created_record_count: u64,
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct AllowlistEntry {
+    pub account_id: AccountId,
+    pub max_position: u128,
+    pub added_at: u64,
 }
 
-#[near]
-impl Contract {
-    #[init]
-    pub fn new(
-        owner_id: AccountId,
-        treasury_wallet: AccountId,
-        growth_wallet: AccountId,
-        reserve_wallet: AccountId,
-        test_mode: bool,
-    ) -> Self {
-        require!(!env::state_exists(), "Already initialized");
+// Capacity for native NEAR deposits via `deposit`, the only deposit path;
+// the contract has no `ft_on_transfer`.
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct DepositCapacity {
+    pub account_id: AccountId,
+    pub launch_stage: LaunchStage,
+    pub allowlisted: bool,
+    pub position_balance: U128,
+    pub max_position: Option<U128>,
+    pub account_remaining: Option<U128>,
+    pub total_locked_position: U128,
+    pub tvl_cap: Option<U128>,
+    pub tvl_remaining: Option<U128>,
+    pub max_locked_position_addable: Option<U128>,
+    pub max_gross_deposit: Option<U128>,
+}
 
-        require!(
-            TREASURY_BPS + GROWTH_BPS + VOLCANO_BPS + RESERVE_BPS + CORE_OPS_BPS
-                == SYSTEM_FEE_BPS,
-            "Fee split must equal 5%"
-        );
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Default)]
+pub struct PauseFlags {
+    pub deposits: bool,
+    pub withdrawals: bool,
+    pub claims: bool,
+}
 
-        require!(
-            CREATE_RECORD_TREASURY_YOCTO + CREATE_RECORD_PRESSURE_YOCTO
-                == CREATE_RECORD_FEE_YOCTO,
-            "Record creation fee split mismatch"
-        );
+impl PauseFlags {
+    pub fn any(&self) -> bool {
+        self.deposits || self.withdrawals || self.claims
+    }
+}
 
-        let operations_wallet: AccountId = OPERATIONS_WALLET
-            .parse()
-            .expect("Invalid operations wallet");
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Default)]
+pub struct VerificationFlags {
+    pub website_public: bool,
+    pub wallet_connection_verified: bool,
+    pub contract_source_verified: bool,
+    pub treasury_wallets_verified: bool,
+}
 
-        let lock_duration_ns = if test_mode {
-            TEST_LOCK_NS
-        } else {
-            PRODUCTION_LOCK_NS
-        };
+// Optional sybil filter applied on top of the position and holding-age rules.
+// The default policy admits everyone.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Default)]
+pub struct EligibilityPolicy {
+    pub min_account_age_ns: u64, // measured from the participant's first deposit
+    pub attester_public_key: Option<PublicKey>,
+    pub attestation_required: bool,
+}
 
-        let exit_window_ns = if test_mode {
-            TEST_EXIT_WINDOW_NS
-        } else {
-            PRODUCTION_EXIT_WINDOW_NS
-        };
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct EligibilityAttestation {
+    pub account_id: AccountId,
+    pub attester_public_key: PublicKey,
+    pub expires_at: u64,
+    pub submitted_at: u64,
+}
 
-        Self {
-            owner_id,
-            operations_wallet,
-            treasury_wallet,
-            growth_wallet,
-            reserve_wallet,
-            test_mode,
-            lock_duration_ns,
-            exit_window_ns,
-            participants: Vec::new(),
-            eruptions: Vec::new(),
-            volcano_pressure: 0,
-            eruption_count: 0,
-            created_record_count: 0,
-        }
-    }
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct AuditAttestation {
+    pub attestation_id: u64,
+    pub auditor: String,
+    pub report_hash: String,
+    pub scope: String,
+    pub recorded_by: AccountId,
+    pub recorded_at: u64,
+}
 
-    #[payable]
-    pub fn deposit(&mut self) {
-        let amount = env::attached_deposit().as_yoctonear();
-        require!(amount > 0, "Attach deposit");
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct TpiDeployment {
+    pub deployment_id: u64,
+    pub venue: String,
+    pub memo: String,
+    pub deployed_amount: u128,
+    pub returned_amount: u128,
+    pub efficiency_bps: u128,
+    pub deployed_by: AccountId,
+    pub deployed_at: u64,
+    pub returned_at: Option<u64>,
+    pub closed: bool,
+    pub excluded_from_trend: bool,
+}
 
-        let caller = env::predecessor_account_id();
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TpiStatus {
+    Full,
+    Reduced,
+    Frozen,
+}
 
-        let treasury = amount * TREASURY_BPS / BPS_DENOMINATOR;
-        let growth = amount * GROWTH_BPS / BPS_DENOMINATOR;
-        let volcano = amount * VOLCANO_BPS / BPS_DENOMINATOR;
-        let reserve = amount * RESERVE_BPS / BPS_DENOMINATOR;
-        let core_ops = amount * CORE_OPS_BPS / BPS_DENOMINATOR;
+impl TpiStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TpiStatus::Full => "FULL",
+            TpiStatus::Reduced => "REDUCED",
+            TpiStatus::Frozen => "FROZEN",
+        }
+    }
 
-        let total_fee = treasury + growth + volcano + reserve + core_ops;
-        let expected_fee = amount * SYSTEM_FEE_BPS / BPS_DENOMINATOR;
-        require!(total_fee <= expected_fee, "Fee math error");
+    pub fn max_deploy_bps(&self) -> u128 {
+        match self {
+            TpiStatus::Full => TPI_FULL_MAX_DEPLOY_BPS,
+            TpiStatus::Reduced => TPI_REDUCED_MAX_DEPLOY_BPS,
+            TpiStatus::Frozen => 0,
+        }
+    }
+}
 
-        let locked_position = amount
-            .checked_sub(total_fee)
-            .expect("Position underflow");
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct TpiState {
+    pub status: TpiStatus,
+    pub trend_window: u64,
+    pub sample_count: u64,
+    pub average_efficiency_bps: u128,
+    pub previous_average_efficiency_bps: Option<u128>,
+    pub trend_delta_bps: i128,
+    pub treasury_bucket: u128,
+    pub max_deploy_bps: u128,
+    pub deployment_power: u128,
+    pub open_deployed: u128,
+}
 
-        Promise::new(self.treasury_wallet.clone())
-            .transfer(NearToken::from_yoctonear(treasury));
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct DepositEvent {
+    pub account_id: AccountId,
+    pub amount: U128,
+    pub fee: U128,
+    pub locked_position: U128,
+    pub treasury: U128,
+    pub growth: U128,
+    pub volcano: U128,
+    pub reserve: U128,
+    pub core_ops: U128,
+    pub pressure: U128,
+    pub participant_count: u64,
+    pub lot_id: u64,
+}
 
-        Promise::new(self.growth_wallet.clone())
-            .transfer(NearToken::from_yoctonear(growth));
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct RecordCreatedEvent {
+    pub creator: AccountId,
+    pub fee: U128,
+    pub treasury: U128,
+    pub pressure_added: U128,
+    pub record_number: u64,
+    pub pressure: U128,
+}
 
-        Promise::new(self.reserve_wallet.clone())
-            .transfer(NearToken::from_yoctonear(reserve));
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct CreateTokenCompatibilityCallEvent {
+    pub note: String,
+}
 
-        Promise::new(self.operations_wallet.clone())
-            .transfer(NearToken::from_yoctonear(core_ops));
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct WithdrawPositionEvent {
+    pub account_id: AccountId,
+    pub gross: U128,
+    pub net_to_user: U128,
+    pub fee: U128,
+    pub treasury: U128,
+    pub growth: U128,
+    pub volcano: U128,
+    pub reserve: U128,
+    pub core_ops: U128,
+    pub remaining_position: U128,
+    pub pressure: U128,
+    pub consumed_lots: Vec<LotConsumption>,
+}
 
-        self.add_or_update_participant(caller.clone(), locked_position);
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EarlyExitEvent {
+    pub account_id: AccountId,
+    pub gross: U128,
+    pub net_to_user: U128,
+    pub fee: U128,
+    pub penalty: U128,
+    pub treasury: U128,
+    pub growth: U128,
+    pub volcano: U128,
+    pub reserve: U128,
+    pub core_ops: U128,
+    pub remaining_position: U128,
+    pub pressure: U128,
+    pub consumed_lots: Vec<EarlyExitLot>,
+}
 
-        self.volcano_pressure = self
-            .volcano_pressure
-            .checked_add(volcano)
-            .expect("Pressure overflow");
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EruptionSnapshotEvent {
+    pub eruption_id: u64,
+    pub phase_number: u64,
+    pub threshold: U128,
+    pub allocated_distribution: U128,
+    pub retained: U128,
+    pub unallocated_remainder: U128,
+    pub share_per_wallet: U128,
+    pub eligible_count: u64,
+    pub carried_pressure: U128,
+    pub next_threshold: U128,
+    pub claim_deadline: u64,
+    pub distribution_mode: DistributionMode,
+    pub eligible_shares: Vec<U128>,
+    pub claim_window_ns: u64,
+}
 
-        self.try_trigger_eruption();
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EruptionClaimedEvent {
+    pub account_id: AccountId,
+    pub eruption_id: u64,
+    pub share: U128,
+    pub claimed_count: u64,
+    pub eligible_count: u64,
+    pub applied_as: ClaimApplication,
+    pub compounded_lot_id: Option<u64>,
+    pub claimed_by: AccountId,
+    pub receiver_id: AccountId,
+}
 
-        env::log_str(&format!(
-            "DEPOSIT caller={} amount={} fee={} locked_position={} treasury={} growth={} volcano={} reserve={} core_ops={} pressure={} participants={}",
-            caller,
-            amount,
-            total_fee,
-            locked_position,
-            treasury,
-            growth,
-            volcano,
-            reserve,
-            core_ops,
-            self.volcano_pressure,
-            self.participants.len()
-        ));
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct BeneficiaryUpdatedEvent {
+    pub account_id: AccountId,
+    pub beneficiary_id: Option<AccountId>,
+    pub inactivity_period_ns: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct InheritanceClaimStartedEvent {
+    pub account_id: AccountId,
+    pub beneficiary_id: AccountId,
+    pub last_active_at: u64,
+    pub executable_at: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct InheritanceClaimCancelledEvent {
+    pub account_id: AccountId,
+    pub beneficiary_id: AccountId,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct ClaimAgentUpdatedEvent {
+    pub account_id: AccountId,
+    pub agent_id: AccountId,
+    pub authorized: bool,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct AutoCompoundUpdatedEvent {
+    pub account_id: AccountId,
+    pub enabled: bool,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct WithdrawalScheduledEvent {
+    pub request_id: u64,
+    pub account_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct WithdrawalCancelledEvent {
+    pub request_id: u64,
+    pub account_id: AccountId,
+    pub reason: String,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct WithdrawalTrimmedEvent {
+    pub request_id: u64,
+    pub account_id: AccountId,
+    pub amount: U128,
+    pub trimmed: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct ScheduledWithdrawalExecutedEvent {
+    pub request_id: u64,
+    pub account_id: AccountId,
+    pub receiver_id: AccountId,
+    pub gross: U128,
+    pub net_to_receiver: U128,
+    pub keeper: AccountId,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EruptionClaimsRolledBackEvent {
+    pub account_id: AccountId,
+    pub claims: Vec<ClaimedShare>,
+    pub total: U128,
+    pub returned_to_pressure: U128,
+    pub pressure: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EruptionProgressEvent {
+    pub phase_number: u64,
+    pub threshold: U128,
+    pub as_of: u64,
+    pub participant_count: u64,
+    pub scanned_from: u64,
+    pub scanned_to: u64,
+    pub eligible_accounts: Vec<AccountId>,
+    pub eligible_positions: Vec<U128>,
+    pub min_holding_age_ns: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EruptionClaimsSweptEvent {
+    pub eruption_id: u64,
+    pub unclaimed_count: u64,
+    pub amount: U128,
+    pub pressure: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct KeeperBudgetFundedEvent {
+    pub funder: AccountId,
+    pub amount: U128,
+    pub keeper_budget: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct CrankExecutedEvent {
+    pub keeper: AccountId,
+    pub sweeps: u64,
+    pub withdrawals: u64,
+    pub eruptions: u64,
+    pub bounty: U128,
+    pub keeper_budget: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct EruptionCheckStoppedEvent {
+    pub reason: String,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct PhaseTableEndEvent {
+    pub phase_number: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct PhaseMilestonesProposedEvent {
+    pub milestones: Vec<U128>,
+    pub executable_at: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct PhaseMilestonesAppendedEvent {
+    pub first_phase_number: u64,
+    pub milestones: Vec<U128>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct LaunchStageAdvancedEvent {
+    pub stage: LaunchStage,
+    pub artifacts: LaunchArtifacts,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct AllowlistUpdatedEvent {
+    pub account_id: AccountId,
+    pub max_position: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct AllowlistRemovedEvent {
+    pub account_id: AccountId,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct TvlCapUpdatedEvent {
+    pub tvl_cap: Option<U128>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct ClaimWindowUpdatedEvent {
+    pub claim_window_ns: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct LegacyLogsUpdatedEvent {
+    pub enabled: bool,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct TpiDeploymentRecordedEvent {
+    pub deployment_id: u64,
+    pub caller: AccountId,
+    pub amount: U128,
+    pub venue: String,
+    pub memo: String,
+    pub total_deployed: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct TpiReturnRecordedEvent {
+    pub deployment_id: u64,
+    pub caller: AccountId,
+    pub deployed: U128,
+    pub returned: U128,
+    pub efficiency_bps: U128,
+    pub total_returned: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct TpiReviewedEvent {
+    pub reviewed_by: AccountId,
+    pub reason: String,
+    pub previous: TpiStatus,
+    pub excluded_samples: u64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct TpiStatusUpdatedEvent {
+    pub previous: TpiStatus,
+    pub status: TpiStatus,
+    pub average_efficiency_bps: U128,
+    pub trend_delta_bps: i64,
+    pub sample_count: u64,
+    pub max_deploy_bps: U128,
+}
+
+// Canonical effect encodings folded into the state hash chain. Variant order is
+// part of the borsh encoding and must only ever be appended to.
+#[near(serializers = [borsh])]
+#[derive(Clone)]
+pub enum StateEffect {
+    Deposit {
+        account_id: AccountId,
+        locked_position: u128,
+        volcano: u128,
+        at: u64,
+    },
+    RecordCreated {
+        creator: AccountId,
+        pressure_added: u128,
+        record_number: u64,
+        at: u64,
+    },
+    WithdrawPosition {
+        account_id: AccountId,
+        gross: u128,
+        volcano: u128,
+        at: u64,
+    },
+    EruptionSnapshot {
+        eruption_id: u64,
+        phase_number: u64,
+        threshold: u128,
+        distribution_pool: u128,
+        retained_amount: u128,
+        unallocated_remainder: u128,
+        share_per_wallet: u128,
+        carried_pressure: u128,
+        claim_deadline: u64,
+        eligible_accounts: Vec<AccountId>,
+        at: u64,
+    },
+    EruptionClaimed {
+        account_id: AccountId,
+        eruption_id: u64,
+        share: u128,
+        at: u64,
+    },
+    EarlyExit {
+        account_id: AccountId,
+        gross: u128,
+        volcano: u128,
+        penalty: u128,
+        at: u64,
+    },
+    ClaimsSwept {
+        eruption_id: u64,
+        amount: u128,
+        at: u64,
+    },
+    EruptionShares {
+        eruption_id: u64,
+        shares: Vec<u128>,
+    },
+    ClaimRolledBack {
+        account_id: AccountId,
+        eruption_id: u64,
+        share: u128,
+        at: u64,
+    },
+}
+
+#[near(serializers = [json])]
+#[derive(Clone)]
+pub struct StateHashChain {
+    pub head: String,
+    pub length: u64,
+}
+
+// NEP-297 events, logged as `EVENT_JSON:{"standard":"near_intersect_volcano",...}`.
+#[near(event_json(standard = "near_intersect_volcano"))]
+pub enum VolcanoEvent {
+    #[event_version("1.1.0")]
+    Deposit(DepositEvent),
+    #[event_version("1.0.0")]
+    RecordCreated(RecordCreatedEvent),
+    #[event_version("1.0.0")]
+    CreateTokenCompatibilityCall(CreateTokenCompatibilityCallEvent),
+    #[event_version("1.1.0")]
+    WithdrawPosition(WithdrawPositionEvent),
+    #[event_version("1.0.0")]
+    EarlyExit(EarlyExitEvent),
+    #[event_version("1.0.0")]
+    WithdrawalScheduled(WithdrawalScheduledEvent),
+    #[event_version("1.0.0")]
+    WithdrawalCancelled(WithdrawalCancelledEvent),
+    #[event_version("1.0.0")]
+    WithdrawalTrimmed(WithdrawalTrimmedEvent),
+    #[event_version("1.0.0")]
+    ScheduledWithdrawalExecuted(ScheduledWithdrawalExecutedEvent),
+    #[event_version("1.0.0")]
+    EruptionClaimsSwept(EruptionClaimsSweptEvent),
+    #[event_version("1.0.0")]
+    EruptionClaimsRolledBack(EruptionClaimsRolledBackEvent),
+    #[event_version("1.0.0")]
+    KeeperBudgetFunded(KeeperBudgetFundedEvent),
+    #[event_version("1.0.0")]
+    CrankExecuted(CrankExecutedEvent),
+    #[event_version("1.2.0")]
+    EruptionProgress(EruptionProgressEvent),
+    #[event_version("1.3.0")]
+    EruptionSnapshot(EruptionSnapshotEvent),
+    #[event_version("1.2.0")]
+    EruptionClaimed(EruptionClaimedEvent),
+    #[event_version("1.0.0")]
+    AutoCompoundUpdated(AutoCompoundUpdatedEvent),
+    #[event_version("1.0.0")]
+    ClaimAgentUpdated(ClaimAgentUpdatedEvent),
+    #[event_version("1.0.0")]
+    BeneficiaryUpdated(BeneficiaryUpdatedEvent),
+    #[event_version("1.0.0")]
+    InheritanceClaimStarted(InheritanceClaimStartedEvent),
+    #[event_version("1.0.0")]
+    InheritanceClaimCancelled(InheritanceClaimCancelledEvent),
+    #[event_version("1.0.0")]
+    EruptionCheckStopped(EruptionCheckStoppedEvent),
+    #[event_version("1.0.0")]
+    PhaseTableEnd(PhaseTableEndEvent),
+    #[event_version("1.0.0")]
+    PhaseMilestonesProposed(PhaseMilestonesProposedEvent),
+    #[event_version("1.0.0")]
+    PhaseMilestonesProposalCancelled(PhaseMilestonesProposedEvent),
+    #[event_version("1.0.0")]
+    PhaseMilestonesAppended(PhaseMilestonesAppendedEvent),
+    #[event_version("1.0.0")]
+    PauseFlagsUpdated(PauseFlags),
+    #[event_version("1.0.0")]
+    VerificationFlagsUpdated(VerificationFlags),
+    #[event_version("1.0.0")]
+    AuditAttestationRecorded(AuditAttestation),
+    #[event_version("1.0.0")]
+    EligibilityPolicyUpdated(EligibilityPolicy),
+    #[event_version("1.0.0")]
+    EligibilityAttested(EligibilityAttestation),
+    #[event_version("1.0.0")]
+    LaunchStageAdvanced(LaunchStageAdvancedEvent),
+    #[event_version("1.0.0")]
+    AllowlistUpdated(AllowlistUpdatedEvent),
+    #[event_version("1.0.0")]
+    AllowlistRemoved(AllowlistRemovedEvent),
+    #[event_version("1.0.0")]
+    TvlCapUpdated(TvlCapUpdatedEvent),
+    #[event_version("1.0.0")]
+    ClaimWindowUpdated(ClaimWindowUpdatedEvent),
+    #[event_version("1.0.0")]
+    LegacyLogsUpdated(LegacyLogsUpdatedEvent),
+    #[event_version("1.0.0")]
+    TpiDeploymentRecorded(TpiDeploymentRecordedEvent),
+    #[event_version("1.0.0")]
+    TpiReturnRecorded(TpiReturnRecordedEvent),
+    #[event_version("1.0.0")]
+    TpiStatusUpdated(TpiStatusUpdatedEvent),
+    #[event_version("1.0.0")]
+    TpiReviewed(TpiReviewedEvent),
+}
+
+#[near(contract_state)]
+#[derive(PanicOnDefault)]
+pub struct Contract {
+    pub owner_id: AccountId,
+
+    pub operations_wallet: AccountId,
+    pub treasury_wallet: AccountId,
+    pub growth_wallet: AccountId,
+    pub reserve_wallet: AccountId,
+
+    pub test_mode: bool,
+    pub lock_duration_ns: u64,
+    pub exit_window_ns: u64,
+
+    pub participants: Vec<Participant>,
+    pub eruptions: Vec<EruptionSnapshot>,
+
+    pub volcano_pressure: u128,
+    pub eruption_count: u64,
+    pub created_record_count: u64,
+
+    pub tpi_deployments: Vec<TpiDeployment>,
+    pub tpi_total_deployed: u128,
+    pub tpi_total_returned: u128,
+    pub tpi_treasury_routed: u128,
+
+    pub launch_stage: LaunchStage,
+    pub launch_artifacts: LaunchArtifacts,
+    pub allowlist: Vec<AllowlistEntry>,
+    pub tvl_cap: Option<u128>,
+    pub pause_flags: PauseFlags,
+    pub verification_flags: VerificationFlags,
+    pub audit_attestations: Vec<AuditAttestation>,
+
+    pub legacy_logs_enabled: bool,
+
+    pub state_hash: [u8; 32],
+    pub state_hash_length: u64,
+
+    pub scheduled_withdrawals: Vec<ScheduledWithdrawal>,
+    pub next_scheduled_withdrawal_id: u64,
+    // Request id the next scan resumes from, so not-due requests at the front
+    // of the queue cannot starve later ones.
+    pub scheduled_withdrawal_cursor: u64,
+
+    pub keeper_budget: u128,
+
+    pub pending_eruption: Option<PendingEruption>,
+
+    pub distribution_mode: DistributionMode,
+
+    pub min_holding_age_ns: u64,
+
+    pub eligibility_policy: EligibilityPolicy,
+    pub eligibility_attestations: Vec<EligibilityAttestation>,
+    pub min_inactivity_period_ns: u64,
+    pub inheritance_challenge_ns: u64,
+    pub phase_milestones: Vec<u128>,
+    pub phase_continuation: Option<PhaseContinuation>,
+    pub governance_timelock_ns: u64,
+    pub pending_phase_proposal: Option<PhaseMilestoneProposal>,
+    pub claim_window_ns: u64,
+}
+
+#[near]
+impl Contract {
+    #[init]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        owner_id: AccountId,
+        treasury_wallet: AccountId,
+        growth_wallet: AccountId,
+        reserve_wallet: AccountId,
+        test_mode: bool,
+        distribution_mode: Option<DistributionMode>,
+        phase_table: Option<PhaseTableConfig>,
+    ) -> Self {
+        require!(!env::state_exists(), "Already initialized");
+
+        let PhaseTableConfig {
+            milestones: phase_milestones,
+            continuation: phase_continuation,
+        } = phase_table.unwrap_or_else(|| PhaseTableConfig {
+            milestones: PHASE_MILESTONES_YOCTO.to_vec(),
+            continuation: None,
+        });
+        require!(!phase_milestones.is_empty(), "Phase milestones must not be empty");
+        assert_increasing_milestones(0, &phase_milestones);
+
+        if let Some(continuation) = &phase_continuation {
+            require!(
+                continuation.ratio_bps > BPS_DENOMINATOR,
+                "Continuation ratio must exceed 10000 bps"
+            );
+        }
+
+        require!(
+            TREASURY_BPS + GROWTH_BPS + VOLCANO_BPS + RESERVE_BPS + CORE_OPS_BPS
+                == SYSTEM_FEE_BPS,
+            "Fee split must equal 5%"
+        );
+
+        require!(
+            CREATE_RECORD_TREASURY_YOCTO + CREATE_RECORD_PRESSURE_YOCTO
+                == CREATE_RECORD_FEE_YOCTO,
+            "Record creation fee split mismatch"
+        );
+
+        let operations_wallet: AccountId = OPERATIONS_WALLET
+            .parse()
+            .expect("Invalid operations wallet");
+
+        let lock_duration_ns = if test_mode {
+            TEST_LOCK_NS
+        } else {
+            PRODUCTION_LOCK_NS
+        };
+
+        let exit_window_ns = if test_mode {
+            TEST_EXIT_WINDOW_NS
+        } else {
+            PRODUCTION_EXIT_WINDOW_NS
+        };
+
+        let min_holding_age_ns = if test_mode {
+            TEST_MIN_HOLDING_AGE_NS
+        } else {
+            PRODUCTION_MIN_HOLDING_AGE_NS
+        };
+
+        let (min_inactivity_period_ns, inheritance_challenge_ns) = if test_mode {
+            (TEST_MIN_INACTIVITY_NS, TEST_INHERITANCE_CHALLENGE_NS)
+        } else {
+            (PRODUCTION_MIN_INACTIVITY_NS, PRODUCTION_INHERITANCE_CHALLENGE_NS)
+        };
+
+        let claim_window_ns = if test_mode {
+            TEST_CLAIM_WINDOW_NS
+        } else {
+            PRODUCTION_CLAIM_WINDOW_NS
+        };
+
+        let governance_timelock_ns = if test_mode {
+            TEST_GOVERNANCE_TIMELOCK_NS
+        } else {
+            PRODUCTION_GOVERNANCE_TIMELOCK_NS
+        };
+
+        Self {
+            owner_id,
+            operations_wallet,
+            treasury_wallet,
+            growth_wallet,
+            reserve_wallet,
+            test_mode,
+            lock_duration_ns,
+            exit_window_ns,
+            participants: Vec::new(),
+            eruptions: Vec::new(),
+            volcano_pressure: 0,
+            eruption_count: 0,
+            created_record_count: 0,
+            tpi_deployments: Vec::new(),
+            tpi_total_deployed: 0,
+            tpi_total_returned: 0,
+            tpi_treasury_routed: 0,
+            launch_stage: LaunchStage::Development,
+            launch_artifacts: LaunchArtifacts::default(),
+            allowlist: Vec::new(),
+            tvl_cap: Some(DEFAULT_BETA_TVL_CAP_YOCTO),
+            pause_flags: PauseFlags::default(),
+            verification_flags: VerificationFlags {
+                website_public: true,
+                ..VerificationFlags::default()
+            },
+            audit_attestations: Vec::new(),
+            legacy_logs_enabled: true,
+            state_hash: [0; 32],
+            state_hash_length: 0,
+            scheduled_withdrawals: Vec::new(),
+            next_scheduled_withdrawal_id: 1,
+            scheduled_withdrawal_cursor: 0,
+            keeper_budget: 0,
+            pending_eruption: None,
+            distribution_mode: distribution_mode.unwrap_or_default(),
+            min_holding_age_ns,
+            eligibility_policy: EligibilityPolicy::default(),
+            eligibility_attestations: Vec::new(),
+            min_inactivity_period_ns,
+            inheritance_challenge_ns,
+            phase_milestones,
+            phase_continuation,
+            governance_timelock_ns,
+            pending_phase_proposal: None,
+            claim_window_ns,
+        }
+    }
+
+    #[payable]
+    pub fn deposit(&mut self) {
+        require!(!self.pause_flags.deposits, "Deposits paused");
+
+        let amount = env::attached_deposit().as_yoctonear();
+        require!(amount > 0, "Attach deposit");
+
+        let caller = self.touch_caller();
+        self.credit_deposit(caller, amount);
     }
 
     #[payable]
     pub fn create_volcano_record(&mut self) {
+        require!(!self.pause_flags.deposits, "Deposits paused");
+
         let amount = env::attached_deposit().as_yoctonear();
 
         require!(
@@ -236,11 +1327,15 @@ impl Contract {
             "Attach exactly 0.032 NEAR"
         );
 
-        let caller = env::predecessor_account_id();
+        let caller = self.touch_caller();
 
-        Promise::new(self.treasury_wallet.clone())
+        self.assert_pre_launch_access(&caller);
+
+        let _ = Promise::new(self.treasury_wallet.clone())
             .transfer(NearToken::from_yoctonear(CREATE_RECORD_TREASURY_YOCTO));
 
+        self.route_to_treasury_bucket(CREATE_RECORD_TREASURY_YOCTO);
+
         self.volcano_pressure = self
             .volcano_pressure
             .checked_add(CREATE_RECORD_PRESSURE_YOCTO)
@@ -248,52 +1343,276 @@ impl Contract {
 
         self.created_record_count += 1;
 
-        self.try_trigger_eruption();
+        self.fold_state_effect(&StateEffect::RecordCreated {
+            creator: caller.clone(),
+            pressure_added: CREATE_RECORD_PRESSURE_YOCTO,
+            record_number: self.created_record_count,
+            at: env::block_timestamp(),
+        });
+
+        VolcanoEvent::RecordCreated(RecordCreatedEvent {
+            creator: caller.clone(),
+            fee: U128(amount),
+            treasury: U128(CREATE_RECORD_TREASURY_YOCTO),
+            pressure_added: U128(CREATE_RECORD_PRESSURE_YOCTO),
+            record_number: self.created_record_count,
+            pressure: U128(self.volcano_pressure),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "VOLCANO_RECORD_CREATED creator={} fee={} treasury={} pressure_added={} record_number={} pressure={} note=not_nep141_token_creation",
+                caller,
+                amount,
+                CREATE_RECORD_TREASURY_YOCTO,
+                CREATE_RECORD_PRESSURE_YOCTO,
+                self.created_record_count,
+                self.volcano_pressure
+            ));
+        }
 
-        env::log_str(&format!(
-            "VOLCANO_RECORD_CREATED creator={} fee={} treasury={} pressure_added={} record_number={} pressure={} note=not_nep141_token_creation",
-            caller,
-            amount,
-            CREATE_RECORD_TREASURY_YOCTO,
-            CREATE_RECORD_PRESSURE_YOCTO,
-            self.created_record_count,
-            self.volcano_pressure
-        ));
+        self.try_trigger_eruption();
     }
 
     #[payable]
     pub fn create_token(&mut self) {
         self.create_volcano_record();
 
-        env::log_str(
-            "CREATE_TOKEN_COMPATIBILITY_CALL note=this_method_creates_a_volcano_record_not_a_nep141_token",
-        );
+        VolcanoEvent::CreateTokenCompatibilityCall(CreateTokenCompatibilityCallEvent {
+            note: "this_method_creates_a_volcano_record_not_a_nep141_token".to_string(),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(
+                "CREATE_TOKEN_COMPATIBILITY_CALL note=this_method_creates_a_volcano_record_not_a_nep141_token",
+            );
+        }
     }
 
     pub fn withdraw_position(&mut self, amount: u128) {
+        require!(!self.pause_flags.withdrawals, "Withdrawals paused");
         require!(amount > 0, "Amount must be greater than zero");
 
-        let caller = env::predecessor_account_id();
+        let caller = self.touch_caller();
         let now = env::block_timestamp();
 
         let index = self
             .participant_index(&caller)
             .expect("Participant not found");
 
-        {
-            let participant = &self.participants[index];
+        self.assert_withdrawable(index, amount, now);
+        self.settle_withdrawal(index, amount, caller, now);
+    }
 
-            require!(participant.active, "Participant inactive");
-            require!(
+    #[payable]
+    pub fn schedule_withdrawal(&mut self, amount: u128, receiver_id: AccountId) -> u64 {
+        require!(
+            amount >= MIN_SCHEDULED_WITHDRAWAL_YOCTO,
+            "Scheduled amount below minimum"
+        );
+
+        let caller = self.touch_caller();
+
+        let index = self
+            .participant_index(&caller)
+            .expect("Participant not found");
+
+        let participant = &self.participants[index];
+        require!(participant.active, "Participant inactive");
+
+        let open_requests = self
+            .scheduled_withdrawals
+            .iter()
+            .filter(|request| request.account_id == caller)
+            .count();
+        require!(
+            open_requests < MAX_SCHEDULED_WITHDRAWALS_PER_ACCOUNT,
+            "Too many scheduled withdrawals"
+        );
+
+        let already_scheduled = self.scheduled_amount(&caller);
+        require!(
+            already_scheduled
+                .checked_add(amount)
+                .expect("Scheduled amount overflow")
+                <= participant.position_balance,
+            "Scheduled withdrawals exceed position balance"
+        );
+
+        let request_id = self.next_scheduled_withdrawal_id;
+        self.next_scheduled_withdrawal_id += 1;
+
+        let mut request = ScheduledWithdrawal {
+            request_id,
+            account_id: caller.clone(),
+            receiver_id: receiver_id.clone(),
+            amount,
+            created_at: env::block_timestamp(),
+            storage_deposit: 0,
+        };
+
+        // State is one borsh blob, so the request grows it by exactly its own
+        // encoded size.
+        let bytes = near_sdk::borsh::to_vec(&request)
+            .expect("Scheduled withdrawal serialization failed")
+            .len() as u128;
+        let storage_cost = env::storage_byte_cost().as_yoctonear() * bytes;
+        let attached = env::attached_deposit().as_yoctonear();
+        require!(attached >= storage_cost, "Attach deposit to cover storage");
+
+        if attached > storage_cost {
+            let _ = Promise::new(caller.clone())
+                .transfer(NearToken::from_yoctonear(attached - storage_cost));
+        }
+
+        request.storage_deposit = storage_cost;
+        self.scheduled_withdrawals.push(request);
+
+        VolcanoEvent::WithdrawalScheduled(WithdrawalScheduledEvent {
+            request_id,
+            account_id: caller,
+            receiver_id,
+            amount: U128(amount),
+        })
+        .emit();
+
+        request_id
+    }
+
+    pub fn cancel_scheduled_withdrawal(&mut self, request_id: u64) {
+        let caller = self.touch_caller();
+        let index = self
+            .scheduled_withdrawals
+            .iter()
+            .position(|request| request.request_id == request_id)
+            .expect("Scheduled withdrawal not found");
+
+        require!(
+            self.scheduled_withdrawals[index].account_id == caller,
+            "Only the scheduling participant"
+        );
+
+        let request = self.remove_scheduled_withdrawal(index);
+
+        VolcanoEvent::WithdrawalCancelled(WithdrawalCancelledEvent {
+            request_id,
+            account_id: request.account_id,
+            reason: "CANCELLED_BY_PARTICIPANT".to_string(),
+        })
+        .emit();
+    }
+
+    // Permissionless: anyone may execute due requests; funds only ever go to
+    // the receiver chosen by the participant.
+    pub fn execute_scheduled_withdrawals(&mut self, max: u64) -> u64 {
+        require!(!self.pause_flags.withdrawals, "Withdrawals paused");
+        require!(max > 0, "Max must be greater than zero");
+
+        let (_, executed, _) = self.process_scheduled_withdrawals(max);
+        executed
+    }
+
+    #[payable]
+    pub fn fund_keeper_budget(&mut self) {
+        let amount = env::attached_deposit().as_yoctonear();
+        require!(amount > 0, "Attach deposit");
+
+        self.keeper_budget = self
+            .keeper_budget
+            .checked_add(amount)
+            .expect("Keeper budget overflow");
+
+        VolcanoEvent::KeeperBudgetFunded(KeeperBudgetFundedEvent {
+            funder: env::predecessor_account_id(),
+            amount: U128(amount),
+            keeper_budget: U128(self.keeper_budget),
+        })
+        .emit();
+    }
+
+    // Permissionless maintenance: claim-expiry sweeps and scheduled-withdrawal
+    // visits share CRANK_MAX_STEPS; a pending eruption then gets its own
+    // ERUPTION_CRANK_WORK_BUDGET participant scans. Only sweeps, executions of
+    // at least MIN_SCHEDULED_WITHDRAWAL_YOCTO and eruption progress earn bounty.
+    pub fn crank(&mut self) -> CrankResult {
+        let keeper = env::predecessor_account_id();
+
+        let sweeps = self.sweep_expired_claims(CRANK_MAX_STEPS);
+        let mut steps = sweeps;
+        let mut paid_steps = sweeps;
+
+        let mut withdrawals = 0;
+        if !self.pause_flags.withdrawals && steps < CRANK_MAX_STEPS {
+            let (withdrawal_steps, executed, paid) =
+                self.process_scheduled_withdrawals(CRANK_MAX_STEPS - steps);
+            steps += withdrawal_steps;
+            paid_steps += paid;
+            withdrawals = executed;
+        }
+
+        let (eruption_work, eruptions) = self.advance_eruptions(ERUPTION_CRANK_WORK_BUDGET);
+        if eruption_work > 0 {
+            steps += eruptions.max(1);
+            paid_steps += eruptions.max(1);
+        }
+
+        let bounty = (paid_steps as u128 * CRANK_BOUNTY_PER_STEP_YOCTO)
+            .min(CRANK_MAX_BOUNTY_YOCTO)
+            .min(self.keeper_budget);
+
+        if bounty > 0 {
+            self.keeper_budget -= bounty;
+
+            let _ = Promise::new(keeper.clone())
+                .transfer(NearToken::from_yoctonear(bounty));
+        }
+
+        if steps > 0 {
+            VolcanoEvent::CrankExecuted(CrankExecutedEvent {
+                keeper,
+                sweeps,
+                withdrawals,
+                eruptions,
+                bounty: U128(bounty),
+                keeper_budget: U128(self.keeper_budget),
+            })
+            .emit();
+        }
+
+        CrankResult {
+            sweeps,
+            withdrawals,
+            eruptions,
+            bounty: U128(bounty),
+        }
+    }
+
+    pub fn early_exit(&mut self, amount: u128) {
+        require!(!self.pause_flags.withdrawals, "Withdrawals paused");
+        require!(amount > 0, "Amount must be greater than zero");
+
+        let caller = self.touch_caller();
+        let now = env::block_timestamp();
+
+        let index = self
+            .participant_index(&caller)
+            .expect("Participant not found");
+
+        {
+            let participant = &self.participants[index];
+
+            require!(participant.active, "Participant inactive");
+            require!(
                 participant.position_balance >= amount,
                 "Insufficient position balance"
             );
-            require!(
-                self.is_exit_window_open_for(participant, now),
-                "Exit window is not open"
-            );
         }
 
+        let plan = self.early_exit_plan(&self.participants[index], amount, now);
+        let penalty: u128 = plan.iter().map(|lot| lot.penalty.0).sum();
+
         let treasury = amount * TREASURY_BPS / BPS_DENOMINATOR;
         let growth = amount * GROWTH_BPS / BPS_DENOMINATOR;
         let volcano = amount * VOLCANO_BPS / BPS_DENOMINATOR;
@@ -303,7 +1622,24 @@ impl Contract {
         let total_fee = treasury + growth + volcano + reserve + core_ops;
         let net_to_user = amount
             .checked_sub(total_fee)
-            .expect("Withdraw fee underflow");
+            .and_then(|v| v.checked_sub(penalty))
+            .expect("Early exit fee underflow");
+
+        // Lots shrink in place; lock starts of the remaining position are untouched.
+        for step in &plan {
+            let lot = self.participants[index]
+                .lots
+                .iter_mut()
+                .find(|lot| lot.lot_id == step.lot_id)
+                .expect("Lot not found");
+
+            lot.amount = lot
+                .amount
+                .checked_sub(step.amount.0)
+                .expect("Lot underflow");
+        }
+
+        self.participants[index].lots.retain(|lot| lot.amount > 0);
 
         self.participants[index].position_balance = self.participants[index]
             .position_balance
@@ -314,52 +1650,298 @@ impl Contract {
             self.participants[index].active = false;
         }
 
-        Promise::new(self.treasury_wallet.clone())
+        let _ = Promise::new(self.treasury_wallet.clone())
             .transfer(NearToken::from_yoctonear(treasury));
 
-        Promise::new(self.growth_wallet.clone())
+        self.route_to_treasury_bucket(treasury);
+
+        let _ = Promise::new(self.growth_wallet.clone())
             .transfer(NearToken::from_yoctonear(growth));
 
-        Promise::new(self.reserve_wallet.clone())
+        let _ = Promise::new(self.reserve_wallet.clone())
             .transfer(NearToken::from_yoctonear(reserve));
 
-        Promise::new(self.operations_wallet.clone())
+        let _ = Promise::new(self.operations_wallet.clone())
             .transfer(NearToken::from_yoctonear(core_ops));
 
-        Promise::new(caller.clone())
+        let _ = Promise::new(caller.clone())
             .transfer(NearToken::from_yoctonear(net_to_user));
 
         self.volcano_pressure = self
             .volcano_pressure
             .checked_add(volcano)
+            .and_then(|v| v.checked_add(penalty))
             .expect("Pressure overflow");
 
-        self.try_trigger_eruption();
-
-        env::log_str(&format!(
-            "WITHDRAW_POSITION caller={} gross={} net_to_user={} fee={} treasury={} growth={} volcano={} reserve={} core_ops={} remaining_position={} pressure={}",
-            caller,
-            amount,
-            net_to_user,
-            total_fee,
-            treasury,
-            growth,
+        self.fold_state_effect(&StateEffect::EarlyExit {
+            account_id: caller.clone(),
+            gross: amount,
             volcano,
-            reserve,
-            core_ops,
-            self.participants[index].position_balance,
-            self.volcano_pressure
-        ));
+            penalty,
+            at: now,
+        });
+
+        VolcanoEvent::EarlyExit(EarlyExitEvent {
+            account_id: caller,
+            gross: U128(amount),
+            net_to_user: U128(net_to_user),
+            fee: U128(total_fee),
+            penalty: U128(penalty),
+            treasury: U128(treasury),
+            growth: U128(growth),
+            volcano: U128(volcano),
+            reserve: U128(reserve),
+            core_ops: U128(core_ops),
+            remaining_position: U128(self.participants[index].position_balance),
+            pressure: U128(self.volcano_pressure),
+            consumed_lots: plan,
+        })
+        .emit();
+
+        self.trim_scheduled_withdrawals(index);
+
+        self.try_trigger_eruption();
     }
 
     pub fn claim_eruption(&mut self, eruption_id: u64) {
+        let caller = self.touch_caller();
+        let compound = self
+            .participant_index(&caller)
+            .is_some_and(|index| self.participants[index].auto_compound);
+
+        self.claim_eruption_for(caller.clone(), eruption_id, compound, caller);
+    }
+
+    // Pays the share to `receiver_id` instead of the participant. Only the
+    // participant can redirect a payout, so this never compounds.
+    pub fn claim_eruption_to(&mut self, eruption_id: u64, receiver_id: AccountId) {
+        let caller = self.touch_caller();
+        self.claim_eruption_for(caller, eruption_id, false, receiver_id);
+    }
+
+    // Lets a registered claim agent trigger a claim. Proceeds always go to the
+    // participant, honouring their auto-compound preference.
+    pub fn claim_eruption_on_behalf(&mut self, account_id: AccountId, eruption_id: u64) {
+        let agent = env::predecessor_account_id();
+        let index = self
+            .participant_index(&account_id)
+            .expect("Participant not found");
+
+        require!(
+            self.participants[index].claim_agents.contains(&agent),
+            "Caller is not a claim agent for this participant"
+        );
+
+        let compound = self.participants[index].auto_compound;
+        self.claim_eruption_for(account_id.clone(), eruption_id, compound, account_id);
+    }
+
+    // Claim agents can only trigger claims paid to the participant; they have
+    // no access to withdrawals or any other participant method.
+    pub fn add_claim_agent(&mut self, agent_id: AccountId) {
+        let caller = self.touch_caller();
+        let index = self
+            .participant_index(&caller)
+            .expect("Participant not found");
+
+        require!(agent_id != caller, "Participant cannot be their own claim agent");
+
+        let agents = &mut self.participants[index].claim_agents;
+        if !agents.contains(&agent_id) {
+            require!(agents.len() < MAX_CLAIM_AGENTS, "Too many claim agents");
+            agents.push(agent_id.clone());
+        }
+
+        VolcanoEvent::ClaimAgentUpdated(ClaimAgentUpdatedEvent {
+            account_id: caller,
+            agent_id,
+            authorized: true,
+        })
+        .emit();
+    }
+
+    pub fn remove_claim_agent(&mut self, agent_id: AccountId) {
+        let caller = self.touch_caller();
+        let index = self
+            .participant_index(&caller)
+            .expect("Participant not found");
+
+        let agents = &mut self.participants[index].claim_agents;
+        let position = agents
+            .iter()
+            .position(|a| a == &agent_id)
+            .expect("Claim agent not found");
+        agents.remove(position);
+
+        VolcanoEvent::ClaimAgentUpdated(ClaimAgentUpdatedEvent {
+            account_id: caller,
+            agent_id,
+            authorized: false,
+        })
+        .emit();
+    }
+
+    // Names who may inherit after `inactivity_period_ns` without any call from
+    // the participant. Passing `None` removes the designation.
+    pub fn set_beneficiary(
+        &mut self,
+        beneficiary_id: Option<AccountId>,
+        inactivity_period_ns: u64,
+    ) {
+        let caller = self.touch_caller();
+
+        let index = self
+            .participant_index(&caller)
+            .expect("Participant not found");
+
+        if let Some(beneficiary_id) = &beneficiary_id {
+            require!(
+                beneficiary_id != &caller,
+                "Participant cannot be their own beneficiary"
+            );
+            require!(
+                inactivity_period_ns >= self.min_inactivity_period_ns,
+                "Inactivity period below minimum"
+            );
+        }
+
+        let participant = &mut self.participants[index];
+        participant.beneficiary_id = beneficiary_id.clone();
+        participant.inactivity_period_ns = if beneficiary_id.is_some() {
+            inactivity_period_ns
+        } else {
+            0
+        };
+
+        VolcanoEvent::BeneficiaryUpdated(BeneficiaryUpdatedEvent {
+            account_id: caller,
+            beneficiary_id,
+            inactivity_period_ns: participant.inactivity_period_ns,
+        })
+        .emit();
+    }
+
+    // Opens the challenge delay. Any call from the participant before it ends
+    // cancels the claim.
+    pub fn start_inheritance_claim(&mut self, account_id: AccountId) {
         let caller = env::predecessor_account_id();
         let now = env::block_timestamp();
 
-        let participant_index = self
+        let index = self
+            .participant_index(&account_id)
+            .expect("Participant not found");
+
+        let participant = &self.participants[index];
+        require!(
+            participant.beneficiary_id.as_ref() == Some(&caller),
+            "Caller is not the beneficiary"
+        );
+        require!(
+            participant.inheritance_claim.is_none(),
+            "Inheritance claim already started"
+        );
+        require!(
+            now >= participant
+                .last_active_at
+                .saturating_add(participant.inactivity_period_ns),
+            "Participant is not inactive"
+        );
+
+        let last_active_at = participant.last_active_at;
+        let executable_at = now
+            .checked_add(self.inheritance_challenge_ns)
+            .expect("Challenge overflow");
+
+        self.participants[index].inheritance_claim = Some(InheritanceClaim {
+            beneficiary_id: caller.clone(),
+            started_at: now,
+            executable_at,
+        });
+
+        VolcanoEvent::InheritanceClaimStarted(InheritanceClaimStartedEvent {
+            account_id,
+            beneficiary_id: caller,
+            last_active_at,
+            executable_at,
+        })
+        .emit();
+    }
+
+    pub fn cancel_inheritance_claim(&mut self) {
+        let caller = env::predecessor_account_id();
+        let index = self
+            .participant_index(&caller)
+            .expect("Participant not found");
+
+        require!(
+            self.participants[index].inheritance_claim.is_some(),
+            "No inheritance claim"
+        );
+
+        self.touch_caller();
+    }
+
+    // After the challenge delay the beneficiary may claim pending eruptions,
+    // paid to themselves.
+    pub fn inherit_eruption_claim(&mut self, account_id: AccountId, eruption_id: u64) {
+        let beneficiary_id = self.assert_inheritance_executable(&account_id);
+        self.claim_eruption_for(account_id, eruption_id, false, beneficiary_id);
+    }
+
+    // Withdraws for the beneficiary under the participant's normal exit-window
+    // and unlocked-lot rules and the usual fee split.
+    pub fn inherit_position(&mut self, account_id: AccountId, amount: u128) {
+        require!(!self.pause_flags.withdrawals, "Withdrawals paused");
+        require!(amount > 0, "Amount must be greater than zero");
+
+        let beneficiary_id = self.assert_inheritance_executable(&account_id);
+        let now = env::block_timestamp();
+
+        let index = self
+            .participant_index(&account_id)
+            .expect("Participant not found");
+
+        self.assert_withdrawable(index, amount, now);
+        self.settle_withdrawal(index, amount, beneficiary_id, now);
+    }
+
+    // Locks the share as a new lot instead of paying it out. It takes the
+    // same 5% fee split as a deposit; there is no compounding exemption.
+    pub fn claim_eruption_and_compound(&mut self, eruption_id: u64) {
+        let caller = self.touch_caller();
+        self.claim_eruption_for(caller.clone(), eruption_id, true, caller);
+    }
+
+    pub fn set_auto_compound(&mut self, enabled: bool) {
+        let caller = self.touch_caller();
+        let index = self
             .participant_index(&caller)
             .expect("Participant not found");
 
+        self.participants[index].auto_compound = enabled;
+
+        VolcanoEvent::AutoCompoundUpdated(AutoCompoundUpdatedEvent {
+            account_id: caller,
+            enabled,
+        })
+        .emit();
+    }
+
+    fn claim_eruption_for(
+        &mut self,
+        account_id: AccountId,
+        eruption_id: u64,
+        compound: bool,
+        receiver_id: AccountId,
+    ) {
+        require!(!self.pause_flags.claims, "Claims paused");
+
+        let now = env::block_timestamp();
+
+        let participant_index = self
+            .participant_index(&account_id)
+            .expect("Participant not found");
+
         let eruption_index = self
             .eruption_index(eruption_id)
             .expect("Eruption not found");
@@ -369,384 +1951,4566 @@ impl Contract {
             "Claim window closed"
         );
 
-        require!(
-            self.eruptions[eruption_index]
-                .eligible_accounts
-                .contains(&caller),
-            "Wallet not eligible for this eruption"
+        require!(
+            self.eruptions[eruption_index]
+                .eligible_accounts
+                .contains(&account_id),
+            "Wallet not eligible for this eruption"
+        );
+
+        require!(
+            !self.participants[participant_index]
+                .claimed_eruption_ids
+                .contains(&eruption_id),
+            "Already claimed"
+        );
+
+        let share = self.eruptions[eruption_index].share_of(&account_id);
+        require!(share > 0, "No claimable share");
+
+        if compound {
+            let lot_id = self.compound_claims(account_id, share);
+            self.record_claim(
+                participant_index,
+                eruption_index,
+                share,
+                now,
+                Some(lot_id),
+                &receiver_id,
+            );
+            return;
+        }
+
+        self.record_claim(participant_index, eruption_index, share, now, None, &receiver_id);
+
+        let _ = Promise::new(receiver_id)
+            .transfer(NearToken::from_yoctonear(share));
+    }
+
+    // Credits claimed shares as a deposit; deposit pauses and caps still apply.
+    fn compound_claims(&mut self, caller: AccountId, amount: u128) -> u64 {
+        require!(!self.pause_flags.deposits, "Deposits paused");
+        self.credit_deposit(caller, amount)
+    }
+
+    // Claims every open eruption the caller is owed, up to `max`, with one
+    // transfer. If the transfer fails the callback reopens all of them. With
+    // auto-compound on, the total is locked as a single lot instead.
+    pub fn claim_all(&mut self, max: u64) -> PromiseOrValue<bool> {
+        require!(!self.pause_flags.claims, "Claims paused");
+        require!(max > 0, "Max must be greater than zero");
+
+        let caller = self.touch_caller();
+        let now = env::block_timestamp();
+
+        let participant_index = self
+            .participant_index(&caller)
+            .expect("Participant not found");
+
+        let claimable = self.claimable_eruptions(&caller, now);
+        require!(!claimable.is_empty(), "Nothing to claim");
+
+        let claimable: Vec<(usize, u128)> = claimable.into_iter().take(max as usize).collect();
+
+        if self.participants[participant_index].auto_compound {
+            let receiver_id = caller.clone();
+            let total = claimable
+                .iter()
+                .try_fold(0u128, |sum, (_, share)| sum.checked_add(*share))
+                .expect("Claim total overflow");
+            let lot_id = self.compound_claims(caller, total);
+
+            for (eruption_index, share) in claimable {
+                self.record_claim(
+                    participant_index,
+                    eruption_index,
+                    share,
+                    now,
+                    Some(lot_id),
+                    &receiver_id,
+                );
+            }
+
+            return PromiseOrValue::Value(true);
+        }
+
+        let mut claims = Vec::new();
+        let mut total: u128 = 0;
+
+        for (eruption_index, share) in claimable {
+            self.record_claim(participant_index, eruption_index, share, now, None, &caller);
+
+            claims.push(ClaimedShare {
+                eruption_id: self.eruptions[eruption_index].eruption_id,
+                share: U128(share),
+            });
+            total = total.checked_add(share).expect("Claim total overflow");
+        }
+
+        Promise::new(caller.clone())
+            .transfer(NearToken::from_yoctonear(total))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(CLAIM_ALL_CALLBACK_GAS)
+                    .on_claim_all_transfer(caller, claims),
+            )
+            .into()
+    }
+
+    #[private]
+    pub fn on_claim_all_transfer(
+        &mut self,
+        account_id: AccountId,
+        claims: Vec<ClaimedShare>,
+    ) -> bool {
+        if env::promise_result_checked(0, 0).is_ok() {
+            return true;
+        }
+
+        self.rollback_claims(&account_id, &claims);
+        false
+    }
+
+    // Milestones can only be appended, after `governance_timelock_ns`, and only
+    // for phases that have not started.
+    pub fn propose_phase_milestones(&mut self, milestones: Vec<u128>) {
+        self.assert_owner();
+        require!(
+            self.pending_phase_proposal.is_none(),
+            "Phase milestone proposal already pending"
+        );
+        self.assert_phase_milestones_appendable(&milestones);
+
+        let now = env::block_timestamp();
+        let executable_at = now
+            .checked_add(self.governance_timelock_ns)
+            .expect("Timelock overflow");
+
+        VolcanoEvent::PhaseMilestonesProposed(PhaseMilestonesProposedEvent {
+            milestones: milestones.iter().copied().map(U128).collect(),
+            executable_at,
+        })
+        .emit();
+
+        self.pending_phase_proposal = Some(PhaseMilestoneProposal {
+            milestones,
+            proposed_at: now,
+            executable_at,
+        });
+    }
+
+    pub fn cancel_phase_milestone_proposal(&mut self) {
+        self.assert_owner();
+
+        let proposal = self
+            .pending_phase_proposal
+            .take()
+            .expect("No phase milestone proposal");
+
+        VolcanoEvent::PhaseMilestonesProposalCancelled(PhaseMilestonesProposedEvent {
+            milestones: proposal.milestones.into_iter().map(U128).collect(),
+            executable_at: proposal.executable_at,
+        })
+        .emit();
+    }
+
+    // Permissionless once the timelock has elapsed.
+    pub fn execute_phase_milestone_proposal(&mut self) {
+        let proposal = self
+            .pending_phase_proposal
+            .take()
+            .expect("No phase milestone proposal");
+
+        require!(
+            env::block_timestamp() >= proposal.executable_at,
+            "Phase milestone timelock not elapsed"
+        );
+        self.assert_phase_milestones_appendable(&proposal.milestones);
+
+        let first_phase_number = self.phase_milestones.len() as u64 + 1;
+        self.phase_milestones.extend(proposal.milestones.iter().copied());
+
+        VolcanoEvent::PhaseMilestonesAppended(PhaseMilestonesAppendedEvent {
+            first_phase_number,
+            milestones: proposal.milestones.into_iter().map(U128).collect(),
+        })
+        .emit();
+    }
+
+    pub fn set_eligibility_policy(
+        &mut self,
+        min_account_age_ns: u64,
+        attester_public_key: Option<PublicKey>,
+        attestation_required: bool,
+    ) {
+        self.assert_owner();
+
+        if let Some(key) = &attester_public_key {
+            require!(
+                key.curve_type() == CurveType::ED25519,
+                "Attester key must be ed25519"
+            );
+        }
+
+        require!(
+            !attestation_required || attester_public_key.is_some(),
+            "Attestation requires an attester key"
+        );
+
+        self.eligibility_policy = EligibilityPolicy {
+            min_account_age_ns,
+            attester_public_key,
+            attestation_required,
+        };
+
+        VolcanoEvent::EligibilityPolicyUpdated(self.eligibility_policy.clone()).emit();
+    }
+
+    // Anyone may relay an attestation; the signature binds it to `account_id`.
+    pub fn submit_eligibility_attestation(
+        &mut self,
+        account_id: AccountId,
+        expires_at: u64,
+        signature: Base64VecU8,
+    ) {
+        let attester_public_key = self
+            .eligibility_policy
+            .attester_public_key
+            .clone()
+            .expect("No attester key configured");
+
+        let now = env::block_timestamp();
+        require!(expires_at > now, "Attestation expired");
+
+        let signature: [u8; 64] = signature
+            .0
+            .as_slice()
+            .try_into()
+            .unwrap_or_else(|_| env::panic_str("Signature must be 64 bytes"));
+
+        let key: &[u8; 32] = attester_public_key.as_bytes()[1..]
+            .try_into()
+            .expect("Invalid attester key");
+
+        require!(
+            env::ed25519_verify(
+                &signature,
+                self.attestation_message(&account_id, expires_at),
+                key
+            ),
+            "Invalid attestation signature"
+        );
+
+        let attestation = EligibilityAttestation {
+            account_id: account_id.clone(),
+            attester_public_key,
+            expires_at,
+            submitted_at: now,
+        };
+
+        match self
+            .eligibility_attestations
+            .iter()
+            .position(|a| a.account_id == account_id)
+        {
+            Some(index) => self.eligibility_attestations[index] = attestation.clone(),
+            None => self.eligibility_attestations.push(attestation.clone()),
+        }
+
+        VolcanoEvent::EligibilityAttested(attestation).emit();
+    }
+
+    pub fn set_pause_flags(&mut self, deposits: bool, withdrawals: bool, claims: bool) {
+        self.assert_owner();
+
+        self.pause_flags = PauseFlags {
+            deposits,
+            withdrawals,
+            claims,
+        };
+
+        VolcanoEvent::PauseFlagsUpdated(self.pause_flags).emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "PAUSE_FLAGS_UPDATED deposits={} withdrawals={} claims={}",
+                deposits, withdrawals, claims
+            ));
+        }
+    }
+
+    pub fn set_legacy_logs_enabled(&mut self, enabled: bool) {
+        self.assert_owner();
+
+        self.legacy_logs_enabled = enabled;
+
+        VolcanoEvent::LegacyLogsUpdated(LegacyLogsUpdatedEvent { enabled }).emit();
+    }
+
+    pub fn set_verification_flags(&mut self, flags: VerificationFlags) {
+        self.assert_owner();
+
+        self.verification_flags = flags;
+
+        VolcanoEvent::VerificationFlagsUpdated(flags).emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "VERIFICATION_FLAGS_UPDATED website_public={} wallet_connection_verified={} contract_source_verified={} treasury_wallets_verified={}",
+                flags.website_public,
+                flags.wallet_connection_verified,
+                flags.contract_source_verified,
+                flags.treasury_wallets_verified
+            ));
+        }
+    }
+
+    pub fn record_audit_attestation(
+        &mut self,
+        auditor: String,
+        report_hash: String,
+        scope: String,
+    ) -> u64 {
+        self.assert_owner();
+
+        require!(!auditor.is_empty(), "Auditor required");
+        require!(auditor.len() <= AUDIT_MAX_AUDITOR_LEN, "Auditor too long");
+        require!(scope.len() <= AUDIT_MAX_SCOPE_LEN, "Scope too long");
+        require!(
+            is_lower_hex(&report_hash, SHA256_HEX_LEN),
+            "Report hash must be 64 lowercase hex characters"
+        );
+
+        let attestation_id = self.audit_attestations.len() as u64 + 1;
+        let caller = env::predecessor_account_id();
+
+        self.audit_attestations.push(AuditAttestation {
+            attestation_id,
+            auditor: auditor.clone(),
+            report_hash: report_hash.clone(),
+            scope: scope.clone(),
+            recorded_by: caller,
+            recorded_at: env::block_timestamp(),
+        });
+
+        VolcanoEvent::AuditAttestationRecorded(
+            self.audit_attestations[self.audit_attestations.len() - 1].clone(),
+        )
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "AUDIT_ATTESTATION_RECORDED attestation_id={} auditor={:?} report_hash={} scope={:?}",
+                attestation_id, auditor, report_hash, scope
+            ));
+        }
+
+        attestation_id
+    }
+
+    pub fn advance_to_audited(
+        &mut self,
+        code_hash: String,
+        commit_hash: String,
+        audit_report_hash: String,
+    ) {
+        self.assert_owner();
+
+        require!(
+            self.launch_stage == LaunchStage::Development,
+            "Launch stage must be DEVELOPMENT"
+        );
+        require!(
+            is_lower_hex(&code_hash, SHA256_HEX_LEN),
+            "Code hash must be 64 lowercase hex characters"
+        );
+        require!(
+            is_lower_hex(&commit_hash, GIT_COMMIT_HEX_LEN),
+            "Commit hash must be 40 lowercase hex characters"
+        );
+        require!(
+            self.audit_attestations
+                .iter()
+                .any(|a| a.report_hash == audit_report_hash),
+            "Audit report hash has no recorded attestation"
+        );
+
+        let now = env::block_timestamp();
+
+        self.launch_artifacts.code_hash = Some(code_hash.clone());
+        self.launch_artifacts.commit_hash = Some(commit_hash.clone());
+        self.launch_artifacts.audit_report_hash = Some(audit_report_hash.clone());
+        self.launch_artifacts.audited_at = Some(now);
+        self.launch_stage = LaunchStage::Audited;
+
+        VolcanoEvent::LaunchStageAdvanced(LaunchStageAdvancedEvent {
+            stage: self.launch_stage,
+            artifacts: self.launch_artifacts.clone(),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "LAUNCH_STAGE_ADVANCED stage={} code_hash={} commit_hash={} audit_report_hash={}",
+                self.launch_stage.as_str(),
+                code_hash,
+                commit_hash,
+                audit_report_hash
+            ));
+        }
+    }
+
+    pub fn advance_to_public(&mut self, contract_account_id: AccountId, explorer_url: String) {
+        self.assert_owner();
+
+        require!(
+            self.launch_stage == LaunchStage::Audited,
+            "Launch stage must be AUDITED"
+        );
+        require!(
+            contract_account_id == env::current_account_id(),
+            "Contract account mismatch"
+        );
+        require!(!explorer_url.is_empty(), "Explorer URL required");
+        require!(
+            explorer_url.len() <= EXPLORER_URL_MAX_LEN,
+            "Explorer URL too long"
+        );
+
+        let flags = self.verification_flags;
+        require!(flags.website_public, "Website not public");
+        require!(
+            flags.wallet_connection_verified,
+            "Wallet connection not verified"
+        );
+        require!(
+            flags.contract_source_verified,
+            "Contract source not verified"
+        );
+        require!(
+            flags.treasury_wallets_verified,
+            "Treasury wallets not verified"
+        );
+
+        self.launch_artifacts.contract_account_id = Some(contract_account_id.clone());
+        self.launch_artifacts.explorer_url = Some(explorer_url.clone());
+        self.launch_artifacts.public_at = Some(env::block_timestamp());
+        self.launch_stage = LaunchStage::Public;
+
+        VolcanoEvent::LaunchStageAdvanced(LaunchStageAdvancedEvent {
+            stage: self.launch_stage,
+            artifacts: self.launch_artifacts.clone(),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "LAUNCH_STAGE_ADVANCED stage={} contract_account_id={} explorer_url={:?}",
+                self.launch_stage.as_str(),
+                contract_account_id,
+                explorer_url
+            ));
+        }
+    }
+
+    pub fn add_to_allowlist(&mut self, account_id: AccountId, max_position: Option<u128>) {
+        self.assert_owner();
+
+        let max_position = max_position.unwrap_or(DEFAULT_BETA_MAX_POSITION_YOCTO);
+
+        match self.allowlist_index(&account_id) {
+            Some(index) => self.allowlist[index].max_position = max_position,
+            None => self.allowlist.push(AllowlistEntry {
+                account_id: account_id.clone(),
+                max_position,
+                added_at: env::block_timestamp(),
+            }),
+        }
+
+        VolcanoEvent::AllowlistUpdated(AllowlistUpdatedEvent {
+            account_id: account_id.clone(),
+            max_position: U128(max_position),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "ALLOWLIST_UPDATED account_id={} max_position={}",
+                account_id, max_position
+            ));
+        }
+    }
+
+    pub fn remove_from_allowlist(&mut self, account_id: AccountId) {
+        self.assert_owner();
+
+        if let Some(index) = self.allowlist_index(&account_id) {
+            self.allowlist.remove(index);
+
+            VolcanoEvent::AllowlistRemoved(AllowlistRemovedEvent {
+                account_id: account_id.clone(),
+            })
+            .emit();
+
+            if self.legacy_logs_enabled {
+                env::log_str(&format!("ALLOWLIST_REMOVED account_id={}", account_id));
+            }
+        }
+    }
+
+    pub fn set_tvl_cap(&mut self, tvl_cap: Option<u128>) {
+        self.assert_owner();
+
+        self.tvl_cap = tvl_cap;
+
+        VolcanoEvent::TvlCapUpdated(TvlCapUpdatedEvent {
+            tvl_cap: tvl_cap.map(U128),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "TVL_CAP_UPDATED tvl_cap={}",
+                tvl_cap.map_or("none".to_string(), |v| v.to_string())
+            ));
+        }
+    }
+
+    // Applies to eruptions snapshotted after this call; existing snapshots keep
+    // the deadline and `claim_window_ns` they recorded.
+    pub fn set_claim_window_ns(&mut self, claim_window_ns: u64) {
+        self.assert_owner();
+        require!(claim_window_ns > 0, "Claim window must be greater than zero");
+
+        self.claim_window_ns = claim_window_ns;
+
+        VolcanoEvent::ClaimWindowUpdated(ClaimWindowUpdatedEvent { claim_window_ns }).emit();
+    }
+
+    pub fn tpi_record_deployment(&mut self, amount: u128, venue: String, memo: String) -> u64 {
+        self.assert_treasury_authority();
+
+        require!(amount > 0, "Amount must be greater than zero");
+        require!(!venue.is_empty(), "Venue required");
+        require!(venue.len() <= TPI_MAX_VENUE_LEN, "Venue too long");
+        require!(memo.len() <= TPI_MAX_MEMO_LEN, "Memo too long");
+
+        let state = self.tpi_state();
+        require!(state.status != TpiStatus::Frozen, "TPI deployment frozen");
+        require!(
+            amount <= state.deployment_power,
+            "Amount exceeds TPI deployment power"
+        );
+
+        let caller = env::predecessor_account_id();
+        let now = env::block_timestamp();
+        let deployment_id = self.tpi_deployments.len() as u64 + 1;
+
+        self.tpi_total_deployed = self
+            .tpi_total_deployed
+            .checked_add(amount)
+            .expect("TPI deployed total overflow");
+
+        self.tpi_deployments.push(TpiDeployment {
+            deployment_id,
+            venue: venue.clone(),
+            memo: memo.clone(),
+            deployed_amount: amount,
+            returned_amount: 0,
+            efficiency_bps: 0,
+            deployed_by: caller.clone(),
+            deployed_at: now,
+            returned_at: None,
+            closed: false,
+            excluded_from_trend: false,
+        });
+
+        VolcanoEvent::TpiDeploymentRecorded(TpiDeploymentRecordedEvent {
+            deployment_id,
+            caller: caller.clone(),
+            amount: U128(amount),
+            venue: venue.clone(),
+            memo: memo.clone(),
+            total_deployed: U128(self.tpi_total_deployed),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "TPI_DEPLOYMENT_RECORDED deployment_id={} caller={} amount={} venue={:?} memo={:?} total_deployed={}",
+                deployment_id,
+                caller,
+                amount,
+                venue,
+                memo,
+                self.tpi_total_deployed
+            ));
+        }
+
+        deployment_id
+    }
+
+    pub fn tpi_record_return(&mut self, deployment_id: u64, amount: u128) {
+        self.assert_treasury_authority();
+
+        let caller = env::predecessor_account_id();
+        let now = env::block_timestamp();
+
+        let index = self
+            .tpi_deployment_index(deployment_id)
+            .expect("Deployment not found");
+
+        require!(!self.tpi_deployments[index].closed, "Deployment already closed");
+
+        let previous_status = self.tpi_state().status;
+        let deployed_amount = self.tpi_deployments[index].deployed_amount;
+        let efficiency_bps = amount
+            .checked_mul(BPS_DENOMINATOR)
+            .expect("Efficiency overflow")
+            / deployed_amount;
+
+        self.tpi_total_returned = self
+            .tpi_total_returned
+            .checked_add(amount)
+            .expect("TPI returned total overflow");
+
+        let deployment = &mut self.tpi_deployments[index];
+        deployment.returned_amount = amount;
+        deployment.efficiency_bps = efficiency_bps;
+        deployment.returned_at = Some(now);
+        deployment.closed = true;
+
+        VolcanoEvent::TpiReturnRecorded(TpiReturnRecordedEvent {
+            deployment_id,
+            caller: caller.clone(),
+            deployed: U128(deployed_amount),
+            returned: U128(amount),
+            efficiency_bps: U128(efficiency_bps),
+            total_returned: U128(self.tpi_total_returned),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "TPI_RETURN_RECORDED deployment_id={} caller={} deployed={} returned={} efficiency_bps={} total_returned={}",
+                deployment_id,
+                caller,
+                deployed_amount,
+                amount,
+                efficiency_bps,
+                self.tpi_total_returned
+            ));
+        }
+
+        self.emit_tpi_status_change(previous_status);
+    }
+
+    // A freeze cannot lift on its own: no deployment can open, so none can
+    // close. After review the owner drops every closed sample from the trend
+    // and deployment power restarts from the no-history status.
+    pub fn tpi_review_reset(&mut self, reason: String) {
+        self.assert_owner();
+
+        require!(!reason.is_empty(), "Reason required");
+        require!(reason.len() <= TPI_MAX_MEMO_LEN, "Reason too long");
+
+        let previous = self.tpi_state().status;
+        require!(previous != TpiStatus::Full, "TPI is not throttled");
+
+        let mut excluded_samples = 0;
+        for deployment in self.tpi_deployments.iter_mut() {
+            if deployment.closed && !deployment.excluded_from_trend {
+                deployment.excluded_from_trend = true;
+                excluded_samples += 1;
+            }
+        }
+
+        VolcanoEvent::TpiReviewed(TpiReviewedEvent {
+            reviewed_by: env::predecessor_account_id(),
+            reason,
+            previous,
+            excluded_samples,
+        })
+        .emit();
+
+        self.emit_tpi_status_change(previous);
+    }
+
+    // Shared by `deposit` and compounded claims: the amount takes the normal
+    // fee split and the remainder is locked as a new lot.
+    fn credit_deposit(&mut self, caller: AccountId, amount: u128) -> u64 {
+        let treasury = amount * TREASURY_BPS / BPS_DENOMINATOR;
+        let growth = amount * GROWTH_BPS / BPS_DENOMINATOR;
+        let volcano = amount * VOLCANO_BPS / BPS_DENOMINATOR;
+        let reserve = amount * RESERVE_BPS / BPS_DENOMINATOR;
+        let core_ops = amount * CORE_OPS_BPS / BPS_DENOMINATOR;
+
+        let total_fee = treasury + growth + volcano + reserve + core_ops;
+        let expected_fee = amount * SYSTEM_FEE_BPS / BPS_DENOMINATOR;
+        require!(total_fee <= expected_fee, "Fee math error");
+
+        let locked_position = amount
+            .checked_sub(total_fee)
+            .expect("Position underflow");
+
+        self.assert_deposit_capacity(&caller, locked_position);
+
+        let _ = Promise::new(self.treasury_wallet.clone())
+            .transfer(NearToken::from_yoctonear(treasury));
+
+        self.route_to_treasury_bucket(treasury);
+
+        let _ = Promise::new(self.growth_wallet.clone())
+            .transfer(NearToken::from_yoctonear(growth));
+
+        let _ = Promise::new(self.reserve_wallet.clone())
+            .transfer(NearToken::from_yoctonear(reserve));
+
+        let _ = Promise::new(self.operations_wallet.clone())
+            .transfer(NearToken::from_yoctonear(core_ops));
+
+        let lot_id = self.add_or_update_participant(caller.clone(), locked_position);
+
+        self.volcano_pressure = self
+            .volcano_pressure
+            .checked_add(volcano)
+            .expect("Pressure overflow");
+
+        self.fold_state_effect(&StateEffect::Deposit {
+            account_id: caller.clone(),
+            locked_position,
+            volcano,
+            at: env::block_timestamp(),
+        });
+
+        VolcanoEvent::Deposit(DepositEvent {
+            account_id: caller.clone(),
+            amount: U128(amount),
+            fee: U128(total_fee),
+            locked_position: U128(locked_position),
+            treasury: U128(treasury),
+            growth: U128(growth),
+            volcano: U128(volcano),
+            reserve: U128(reserve),
+            core_ops: U128(core_ops),
+            pressure: U128(self.volcano_pressure),
+            participant_count: self.participants.len() as u64,
+            lot_id,
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "DEPOSIT caller={} amount={} fee={} locked_position={} treasury={} growth={} volcano={} reserve={} core_ops={} pressure={} participants={}",
+                caller,
+                amount,
+                total_fee,
+                locked_position,
+                treasury,
+                growth,
+                volcano,
+                reserve,
+                core_ops,
+                self.volcano_pressure,
+                self.participants.len()
+            ));
+        }
+
+        self.try_trigger_eruption();
+
+        lot_id
+    }
+
+    fn record_claim(
+        &mut self,
+        participant_index: usize,
+        eruption_index: usize,
+        share: u128,
+        now: u64,
+        compounded_lot_id: Option<u64>,
+        receiver_id: &AccountId,
+    ) {
+        let caller = self.participants[participant_index].account_id.clone();
+        let eruption_id = self.eruptions[eruption_index].eruption_id;
+
+        self.participants[participant_index]
+            .claimed_eruption_ids
+            .push(eruption_id);
+
+        self.eruptions[eruption_index].claimed_count += 1;
+        self.eruptions[eruption_index].claimed_amount += share;
+
+        self.fold_state_effect(&StateEffect::EruptionClaimed {
+            account_id: caller.clone(),
+            eruption_id,
+            share,
+            at: now,
+        });
+
+        VolcanoEvent::EruptionClaimed(EruptionClaimedEvent {
+            account_id: caller.clone(),
+            eruption_id,
+            share: U128(share),
+            claimed_count: self.eruptions[eruption_index].claimed_count,
+            eligible_count: self.eruptions[eruption_index].eligible_count,
+            applied_as: if compounded_lot_id.is_some() {
+                ClaimApplication::Compounded
+            } else {
+                ClaimApplication::Transferred
+            },
+            compounded_lot_id,
+            claimed_by: env::predecessor_account_id(),
+            receiver_id: receiver_id.clone(),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "ERUPTION_CLAIMED caller={} eruption_id={} share={} claimed_count={} eligible_count={}",
+                caller,
+                eruption_id,
+                share,
+                self.eruptions[eruption_index].claimed_count,
+                self.eruptions[eruption_index].eligible_count
+            ));
+        }
+    }
+
+    // Reopens claims whose payout failed. A share whose eruption was swept in
+    // the meantime goes to volcano pressure like any other unclaimed share.
+    fn rollback_claims(&mut self, account_id: &AccountId, claims: &[ClaimedShare]) {
+        let now = env::block_timestamp();
+        let participant_index = self
+            .participant_index(account_id)
+            .expect("Participant not found");
+
+        let mut total: u128 = 0;
+        let mut returned_to_pressure: u128 = 0;
+
+        for claim in claims {
+            let eruption_index = self
+                .eruption_index(claim.eruption_id)
+                .expect("Eruption not found");
+
+            self.participants[participant_index]
+                .claimed_eruption_ids
+                .retain(|id| *id != claim.eruption_id);
+
+            let eruption = &mut self.eruptions[eruption_index];
+            eruption.claimed_count -= 1;
+            eruption.claimed_amount -= claim.share.0;
+
+            if eruption.swept {
+                returned_to_pressure += claim.share.0;
+            }
+
+            total += claim.share.0;
+
+            self.fold_state_effect(&StateEffect::ClaimRolledBack {
+                account_id: account_id.clone(),
+                eruption_id: claim.eruption_id,
+                share: claim.share.0,
+                at: now,
+            });
+        }
+
+        if returned_to_pressure > 0 {
+            self.volcano_pressure = self
+                .volcano_pressure
+                .checked_add(returned_to_pressure)
+                .expect("Pressure overflow");
+        }
+
+        VolcanoEvent::EruptionClaimsRolledBack(EruptionClaimsRolledBackEvent {
+            account_id: account_id.clone(),
+            claims: claims.to_vec(),
+            total: U128(total),
+            returned_to_pressure: U128(returned_to_pressure),
+            pressure: U128(self.volcano_pressure),
+        })
+        .emit();
+
+        if returned_to_pressure > 0 {
+            self.try_trigger_eruption();
+        }
+    }
+
+    fn claimable_eruptions(&self, account_id: &AccountId, now: u64) -> Vec<(usize, u128)> {
+        let claimed = match self.participant_index(account_id) {
+            Some(index) => &self.participants[index].claimed_eruption_ids,
+            None => return Vec::new(),
+        };
+
+        self.eruptions
+            .iter()
+            .enumerate()
+            .filter(|(_, eruption)| {
+                now <= eruption.claim_deadline && !claimed.contains(&eruption.eruption_id)
+            })
+            .map(|(index, eruption)| (index, eruption.share_of(account_id)))
+            .filter(|(_, share)| *share > 0)
+            .collect()
+    }
+
+    fn try_trigger_eruption(&mut self) {
+        self.advance_eruptions(ERUPTION_INLINE_WORK_BUDGET);
+    }
+
+    // Eligibility is scanned in chunks so a call that crosses one or more
+    // milestones stays within `budget` units of work (one per participant
+    // scanned, one per snapshot). Unfinished scans resume on the next call.
+    // Returns (work done, snapshots completed).
+    fn advance_eruptions(&mut self, budget: u64) -> (u64, u64) {
+        let mut work = 0;
+        let mut completed = 0;
+
+        while work < budget {
+            if self.pending_eruption.is_none() {
+                let phase_index = self.eruption_count as usize;
+
+                let threshold = match self.phase_threshold_by_index(phase_index) {
+                    Some(value) => value,
+                    None => {
+                        VolcanoEvent::EruptionCheckStopped(EruptionCheckStoppedEvent {
+                            reason: "no_more_supported_phases".to_string(),
+                        })
+                        .emit();
+
+                        if self.legacy_logs_enabled {
+                            env::log_str("ERUPTION_CHECK_STOPPED reason=no_more_supported_phases");
+                        }
+                        break;
+                    }
+                };
+
+                if self.volcano_pressure < threshold {
+                    break;
+                }
+
+                // Only participants known when the milestone was crossed can
+                // be eligible. Each is judged when the scan reaches them: lots
+                // aged by `as_of` count, less anything exited since then.
+                self.pending_eruption = Some(PendingEruption {
+                    phase_number: self.eruption_count + 1,
+                    threshold,
+                    as_of: env::block_timestamp(),
+                    participant_count: self.participants.len() as u64,
+                    cursor: 0,
+                    eligible_accounts: Vec::new(),
+                    eligible_positions: Vec::new(),
+                });
+            }
+
+            let mut pending = self.pending_eruption.take().expect("Pending eruption");
+
+            let from = pending.cursor;
+            let to = pending
+                .participant_count
+                .min(from + (budget - work));
+
+            let (found, positions): (Vec<AccountId>, Vec<u128>) = self.participants
+                [from as usize..to as usize]
+                .iter()
+                .filter_map(|p| {
+                    self.eligible_position(p, pending.as_of)
+                        .map(|held| (p.account_id.clone(), held))
+                })
+                .unzip();
+
+            work += to - from;
+            pending.cursor = to;
+            pending.eligible_accounts.extend(found.iter().cloned());
+            pending.eligible_positions.extend(positions.iter().copied());
+
+            if to > from || from == 0 {
+                VolcanoEvent::EruptionProgress(EruptionProgressEvent {
+                    phase_number: pending.phase_number,
+                    threshold: U128(pending.threshold),
+                    as_of: pending.as_of,
+                    participant_count: pending.participant_count,
+                    scanned_from: from,
+                    scanned_to: to,
+                    eligible_accounts: found,
+                    eligible_positions: positions.into_iter().map(U128).collect(),
+                    min_holding_age_ns: self.min_holding_age_ns,
+                })
+                .emit();
+            }
+
+            if pending.cursor < pending.participant_count || work >= budget {
+                self.pending_eruption = Some(pending);
+                break;
+            }
+
+            work += 1;
+            completed += 1;
+
+            self.trigger_eruption_for_phase(
+                self.eruption_count as usize,
+                pending.threshold,
+                pending.eligible_accounts,
+                pending.eligible_positions,
+            );
+        }
+
+        (work, completed)
+    }
+
+    fn trigger_eruption_for_phase(
+        &mut self,
+        phase_index: usize,
+        threshold: u128,
+        eligible_accounts: Vec<AccountId>,
+        eligible_positions: Vec<u128>,
+    ) {
+        require!(self.volcano_pressure >= threshold, "Threshold not reached");
+
+        let phase_number = self.eruption_count + 1;
+        let now = env::block_timestamp();
+
+        let distribution_pool = distribution_pool_for(phase_number, threshold);
+
+        let retained_amount = threshold
+            .checked_sub(distribution_pool)
+            .expect("Retained underflow");
+
+        let eligible_count = eligible_accounts.len() as u64;
+
+        // Position-weighted shares are rounded down per wallet; the dust joins
+        // the unallocated remainder exactly as in equal-share mode.
+        let (share_per_wallet, eligible_shares) = match self.distribution_mode {
+            DistributionMode::EqualShare => {
+                let share = if eligible_count > 0 {
+                    distribution_pool / eligible_count as u128
+                } else {
+                    0
+                };
+                (share, Vec::new())
+            }
+            DistributionMode::PositionWeighted => {
+                let total: u128 = eligible_positions.iter().sum();
+                let shares = eligible_positions
+                    .iter()
+                    .map(|position| mul_div(distribution_pool, *position, total))
+                    .collect();
+                (0, shares)
+            }
+        };
+
+        let allocated_claim_pool = match self.distribution_mode {
+            DistributionMode::EqualShare => share_per_wallet * eligible_count as u128,
+            DistributionMode::PositionWeighted => eligible_shares.iter().sum(),
+        };
+        let unallocated_remainder = distribution_pool
+            .checked_sub(allocated_claim_pool)
+            .expect("Remainder underflow");
+
+        let extra_pressure = self
+            .volcano_pressure
+            .checked_sub(threshold)
+            .expect("Extra pressure underflow");
+
+        self.volcano_pressure = retained_amount
+            .checked_add(unallocated_remainder)
+            .and_then(|v| v.checked_add(extra_pressure))
+            .expect("Pressure carry overflow");
+
+        let eruption_id = self.eruption_count + 1;
+
+        let claim_deadline = now
+            .checked_add(self.claim_window_ns)
+            .expect("Claim deadline overflow");
+
+        self.fold_state_effect(&StateEffect::EruptionSnapshot {
+            eruption_id,
+            phase_number,
+            threshold,
+            distribution_pool: allocated_claim_pool,
+            retained_amount,
+            unallocated_remainder,
+            share_per_wallet,
+            carried_pressure: self.volcano_pressure,
+            claim_deadline,
+            eligible_accounts: eligible_accounts.clone(),
+            at: now,
+        });
+
+        if self.distribution_mode == DistributionMode::PositionWeighted {
+            self.fold_state_effect(&StateEffect::EruptionShares {
+                eruption_id,
+                shares: eligible_shares.clone(),
+            });
+        }
+
+        let snapshot = EruptionSnapshot {
+            eruption_id,
+            phase_number,
+            threshold,
+            distribution_pool: allocated_claim_pool,
+            retained_amount,
+            unallocated_remainder,
+            share_per_wallet,
+            eligible_count,
+            claimed_count: 0,
+            created_at: now,
+            claim_deadline,
+            eligible_accounts,
+            swept: false,
+            distribution_mode: self.distribution_mode,
+            eligible_shares: eligible_shares.clone(),
+            claimed_amount: 0,
+            claim_window_ns: self.claim_window_ns,
+        };
+
+        self.eruptions.push(snapshot);
+        self.eruption_count += 1;
+
+        VolcanoEvent::EruptionSnapshot(EruptionSnapshotEvent {
+            eruption_id,
+            phase_number,
+            threshold: U128(threshold),
+            allocated_distribution: U128(allocated_claim_pool),
+            retained: U128(retained_amount),
+            unallocated_remainder: U128(unallocated_remainder),
+            share_per_wallet: U128(share_per_wallet),
+            eligible_count,
+            carried_pressure: U128(self.volcano_pressure),
+            next_threshold: U128(self.get_next_eruption_threshold()),
+            claim_deadline,
+            distribution_mode: self.distribution_mode,
+            eligible_shares: eligible_shares.into_iter().map(U128).collect(),
+            claim_window_ns: self.claim_window_ns,
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "ERUPTION_SNAPSHOT eruption_id={} phase={} threshold={} allocated_distribution={} retained={} unallocated_remainder={} share_per_wallet={} eligible_count={} carried_pressure={} next_threshold={}",
+                eruption_id,
+                phase_number,
+                threshold,
+                allocated_claim_pool,
+                retained_amount,
+                unallocated_remainder,
+                share_per_wallet,
+                eligible_count,
+                self.volcano_pressure,
+                self.get_next_eruption_threshold()
+            ));
+        }
+
+        let next_index = phase_index + 1;
+        if self.phase_threshold_by_index(next_index).is_none() {
+            VolcanoEvent::PhaseTableEnd(PhaseTableEndEvent {
+                phase_number,
+            })
+            .emit();
+
+            if self.legacy_logs_enabled {
+                env::log_str(&format!(
+                    "ERUPTION_PHASE_TABLE_END reached=phase_{} no_future_phase_promised",
+                    phase_number
+                ));
+            }
+        }
+    }
+
+    fn add_or_update_participant(&mut self, account_id: AccountId, locked_position: u128) -> u64 {
+        let now = env::block_timestamp();
+
+        let index = match self.participant_index(&account_id) {
+            Some(index) => {
+                // A fully exited participant starts a fresh tenure; each lot
+                // still carries its own lock, so nothing old is reused.
+                if !self.participants[index].active {
+                    self.participants[index].lock_started_at = now;
+                }
+
+                self.participants[index].active = true;
+                self.participants[index].position_balance = self.participants[index]
+                    .position_balance
+                    .checked_add(locked_position)
+                    .expect("Position overflow");
+
+                self.participants[index].total_deposited = self.participants[index]
+                    .total_deposited
+                    .checked_add(locked_position)
+                    .expect("Deposit total overflow");
+
+                index
+            }
+            None => {
+                self.participants.push(Participant {
+                    account_id,
+                    active: true,
+                    position_balance: locked_position,
+                    total_deposited: locked_position,
+                    created_at: now,
+                    lock_started_at: now,
+                    claimed_eruption_ids: Vec::new(),
+                    lots: Vec::new(),
+                    next_lot_id: 1,
+                    auto_compound: false,
+                    claim_agents: Vec::new(),
+                    beneficiary_id: None,
+                    inactivity_period_ns: 0,
+                    last_active_at: now,
+                    inheritance_claim: None,
+                });
+
+                self.participants.len() - 1
+            }
+        };
+
+        let participant = &mut self.participants[index];
+        let lot_id = participant.next_lot_id;
+        participant.next_lot_id += 1;
+
+        participant.lots.push(PositionLot {
+            lot_id,
+            amount: locked_position,
+            original_amount: locked_position,
+            lock_started_at: now,
+        });
+
+        lot_id
+    }
+
+    // Oldest unlocked lots are consumed first; emptied lots are dropped.
+    fn consume_unlocked_lots(
+        &mut self,
+        index: usize,
+        amount: u128,
+        now: u64,
+    ) -> Vec<LotConsumption> {
+        let open: Vec<bool> = self.participants[index]
+            .lots
+            .iter()
+            .map(|lot| self.is_lock_window_open(lot.lock_started_at, now))
+            .collect();
+
+        let mut remaining = amount;
+        let mut consumed = Vec::new();
+
+        for (lot, is_open) in self.participants[index].lots.iter_mut().zip(open) {
+            if remaining == 0 {
+                break;
+            }
+
+            if !is_open || lot.amount == 0 {
+                continue;
+            }
+
+            let take = lot.amount.min(remaining);
+            lot.amount -= take;
+            remaining -= take;
+
+            consumed.push(LotConsumption {
+                lot_id: lot.lot_id,
+                amount: U128(take),
+            });
+        }
+
+        require!(remaining == 0, "Amount exceeds unlocked lots");
+
+        self.participants[index].lots.retain(|lot| lot.amount > 0);
+
+        consumed
+    }
+
+    fn assert_withdrawable(&self, index: usize, amount: u128, now: u64) {
+        let participant = &self.participants[index];
+
+        require!(participant.active, "Participant inactive");
+        require!(
+            participant.position_balance >= amount,
+            "Insufficient position balance"
+        );
+        require!(
+            self.is_exit_window_open_for(participant, now),
+            "Exit window is not open"
+        );
+        require!(
+            self.unlocked_amount(participant, now) >= amount,
+            "Amount exceeds unlocked lots"
+        );
+    }
+
+    // Called by every participant entry point once its guards pass. Any call
+    // by the participant proves they still hold their keys, so it also cancels
+    // a pending inheritance claim.
+    fn touch_caller(&mut self) -> AccountId {
+        let caller = env::predecessor_account_id();
+
+        let Some(index) = self.participant_index(&caller) else {
+            return caller;
+        };
+
+        let participant = &mut self.participants[index];
+        participant.last_active_at = env::block_timestamp();
+
+        if let Some(claim) = participant.inheritance_claim.take() {
+            VolcanoEvent::InheritanceClaimCancelled(InheritanceClaimCancelledEvent {
+                account_id: caller.clone(),
+                beneficiary_id: claim.beneficiary_id,
+            })
+            .emit();
+        }
+
+        caller
+    }
+
+    fn assert_inheritance_executable(&self, account_id: &AccountId) -> AccountId {
+        let caller = env::predecessor_account_id();
+        let index = self
+            .participant_index(account_id)
+            .expect("Participant not found");
+
+        let claim = self.participants[index]
+            .inheritance_claim
+            .as_ref()
+            .expect("No inheritance claim");
+
+        require!(claim.beneficiary_id == caller, "Caller is not the beneficiary");
+        require!(
+            env::block_timestamp() >= claim.executable_at,
+            "Inheritance challenge delay not elapsed"
+        );
+
+        caller
+    }
+
+    fn settle_withdrawal(
+        &mut self,
+        index: usize,
+        amount: u128,
+        receiver_id: AccountId,
+        now: u64,
+    ) -> u128 {
+        let caller = self.participants[index].account_id.clone();
+
+        let treasury = amount * TREASURY_BPS / BPS_DENOMINATOR;
+        let growth = amount * GROWTH_BPS / BPS_DENOMINATOR;
+        let volcano = amount * VOLCANO_BPS / BPS_DENOMINATOR;
+        let reserve = amount * RESERVE_BPS / BPS_DENOMINATOR;
+        let core_ops = amount * CORE_OPS_BPS / BPS_DENOMINATOR;
+
+        let total_fee = treasury + growth + volcano + reserve + core_ops;
+        let net_to_user = amount
+            .checked_sub(total_fee)
+            .expect("Withdraw fee underflow");
+
+        let consumed_lots = self.consume_unlocked_lots(index, amount, now);
+
+        self.participants[index].position_balance = self.participants[index]
+            .position_balance
+            .checked_sub(amount)
+            .expect("Position underflow");
+
+        if self.participants[index].position_balance == 0 {
+            self.participants[index].active = false;
+        }
+
+        let _ = Promise::new(self.treasury_wallet.clone())
+            .transfer(NearToken::from_yoctonear(treasury));
+
+        self.route_to_treasury_bucket(treasury);
+
+        let _ = Promise::new(self.growth_wallet.clone())
+            .transfer(NearToken::from_yoctonear(growth));
+
+        let _ = Promise::new(self.reserve_wallet.clone())
+            .transfer(NearToken::from_yoctonear(reserve));
+
+        let _ = Promise::new(self.operations_wallet.clone())
+            .transfer(NearToken::from_yoctonear(core_ops));
+
+        let _ = Promise::new(receiver_id)
+            .transfer(NearToken::from_yoctonear(net_to_user));
+
+        self.volcano_pressure = self
+            .volcano_pressure
+            .checked_add(volcano)
+            .expect("Pressure overflow");
+
+        self.fold_state_effect(&StateEffect::WithdrawPosition {
+            account_id: caller.clone(),
+            gross: amount,
+            volcano,
+            at: now,
+        });
+
+        VolcanoEvent::WithdrawPosition(WithdrawPositionEvent {
+            account_id: caller.clone(),
+            gross: U128(amount),
+            net_to_user: U128(net_to_user),
+            fee: U128(total_fee),
+            treasury: U128(treasury),
+            growth: U128(growth),
+            volcano: U128(volcano),
+            reserve: U128(reserve),
+            core_ops: U128(core_ops),
+            remaining_position: U128(self.participants[index].position_balance),
+            pressure: U128(self.volcano_pressure),
+            consumed_lots,
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "WITHDRAW_POSITION caller={} gross={} net_to_user={} fee={} treasury={} growth={} volcano={} reserve={} core_ops={} remaining_position={} pressure={}",
+                caller,
+                amount,
+                net_to_user,
+                total_fee,
+                treasury,
+                growth,
+                volcano,
+                reserve,
+                core_ops,
+                self.participants[index].position_balance,
+                self.volcano_pressure
+            ));
+        }
+
+        self.trim_scheduled_withdrawals(index);
+        self.try_trigger_eruption();
+
+        net_to_user
+    }
+
+    // Visits up to `max` requests in id order, resuming after the last request
+    // the previous scan reached and wrapping once, and returns
+    // (steps, executed, paid). Every visit is a step; requests the position can
+    // no longer cover are dropped rather than left to block the queue. Only
+    // executions of at least MIN_SCHEDULED_WITHDRAWAL_YOCTO count as paid.
+    fn process_scheduled_withdrawals(&mut self, max: u64) -> (u64, u64, u64) {
+        let now = env::block_timestamp();
+        let keeper = env::predecessor_account_id();
+
+        let visits = max.min(self.scheduled_withdrawals.len() as u64);
+        let mut next_id = self.scheduled_withdrawal_cursor;
+
+        let mut steps = 0;
+        let mut executed = 0;
+        let mut paid = 0;
+
+        while steps < visits && !self.scheduled_withdrawals.is_empty() {
+            // Requests stay sorted by id, and removals elsewhere (trims) may
+            // shift positions, so look the cursor up again on every visit.
+            let mut cursor = self
+                .scheduled_withdrawals
+                .partition_point(|request| request.request_id < next_id);
+            if cursor == self.scheduled_withdrawals.len() {
+                cursor = 0;
+            }
+
+            let request = self.scheduled_withdrawals[cursor].clone();
+            next_id = request.request_id + 1;
+            steps += 1;
+
+            let index = match self.participant_index(&request.account_id) {
+                Some(index)
+                    if self.participants[index].active
+                        && self.participants[index].position_balance >= request.amount =>
+                {
+                    index
+                }
+                _ => {
+                    self.remove_scheduled_withdrawal(cursor);
+
+                    VolcanoEvent::WithdrawalCancelled(WithdrawalCancelledEvent {
+                        request_id: request.request_id,
+                        account_id: request.account_id,
+                        reason: "INSUFFICIENT_POSITION".to_string(),
+                    })
+                    .emit();
+
+                    continue;
+                }
+            };
+
+            if !self.is_withdrawal_due(&self.participants[index], request.amount, now) {
+                continue;
+            }
+
+            self.remove_scheduled_withdrawal(cursor);
+
+            let net_to_receiver =
+                self.settle_withdrawal(index, request.amount, request.receiver_id.clone(), now);
+
+            VolcanoEvent::ScheduledWithdrawalExecuted(ScheduledWithdrawalExecutedEvent {
+                request_id: request.request_id,
+                account_id: request.account_id,
+                receiver_id: request.receiver_id,
+                gross: U128(request.amount),
+                net_to_receiver: U128(net_to_receiver),
+                keeper: keeper.clone(),
+            })
+            .emit();
+
+            executed += 1;
+            if request.amount >= MIN_SCHEDULED_WITHDRAWAL_YOCTO {
+                paid += 1;
+            }
+        }
+
+        self.scheduled_withdrawal_cursor = next_id;
+
+        (steps, executed, paid)
+    }
+
+    fn is_withdrawal_due(&self, participant: &Participant, amount: u128, now: u64) -> bool {
+        self.is_exit_window_open_for(participant, now)
+            && self.unlocked_amount(participant, now) >= amount
+    }
+
+    fn is_withdrawal_actionable(&self, request: &ScheduledWithdrawal, now: u64) -> bool {
+        match self.participant_index(&request.account_id) {
+            Some(index) => {
+                let participant = &self.participants[index];
+
+                !participant.active
+                    || participant.position_balance < request.amount
+                    || self.is_withdrawal_due(participant, request.amount, now)
+            }
+            None => true,
+        }
+    }
+
+    fn is_sweepable(eruption: &EruptionSnapshot, now: u64) -> bool {
+        !eruption.swept && now > eruption.claim_deadline
+    }
+
+    // Unclaimed shares of an expired eruption go back into volcano pressure.
+    fn sweep_expired_claims(&mut self, max: u64) -> u64 {
+        let now = env::block_timestamp();
+        let mut swept = 0;
+
+        while swept < max {
+            let eruption_index = match self
+                .eruptions
+                .iter()
+                .position(|eruption| Self::is_sweepable(eruption, now))
+            {
+                Some(index) => index,
+                None => break,
+            };
+
+            let eruption = &mut self.eruptions[eruption_index];
+            eruption.swept = true;
+
+            let eruption_id = eruption.eruption_id;
+            let unclaimed_count = eruption
+                .eligible_count
+                .checked_sub(eruption.claimed_count)
+                .expect("Claimed count overflow");
+            let amount = eruption
+                .distribution_pool
+                .checked_sub(eruption.claimed_amount)
+                .expect("Claimed amount overflow");
+
+            self.volcano_pressure = self
+                .volcano_pressure
+                .checked_add(amount)
+                .expect("Pressure overflow");
+
+            self.fold_state_effect(&StateEffect::ClaimsSwept {
+                eruption_id,
+                amount,
+                at: now,
+            });
+
+            VolcanoEvent::EruptionClaimsSwept(EruptionClaimsSweptEvent {
+                eruption_id,
+                unclaimed_count,
+                amount: U128(amount),
+                pressure: U128(self.volcano_pressure),
+            })
+            .emit();
+
+            self.try_trigger_eruption();
+
+            swept += 1;
+        }
+
+        swept
+    }
+
+    // Cuts scheduled requests back to the remaining position, newest first, so
+    // the earliest requests keep their full amounts.
+    fn trim_scheduled_withdrawals(&mut self, index: usize) {
+        let account_id = self.participants[index].account_id.clone();
+        let position_balance = self.participants[index].position_balance;
+
+        let mut excess = self
+            .scheduled_amount(&account_id)
+            .saturating_sub(position_balance);
+        let mut cursor = self.scheduled_withdrawals.len();
+
+        while excess > 0 && cursor > 0 {
+            cursor -= 1;
+
+            if self.scheduled_withdrawals[cursor].account_id != account_id {
+                continue;
+            }
+
+            let request_id = self.scheduled_withdrawals[cursor].request_id;
+            let amount = self.scheduled_withdrawals[cursor].amount;
+
+            if amount <= excess {
+                excess -= amount;
+                self.remove_scheduled_withdrawal(cursor);
+
+                VolcanoEvent::WithdrawalCancelled(WithdrawalCancelledEvent {
+                    request_id,
+                    account_id: account_id.clone(),
+                    reason: "INSUFFICIENT_POSITION".to_string(),
+                })
+                .emit();
+            } else {
+                self.scheduled_withdrawals[cursor].amount = amount - excess;
+
+                VolcanoEvent::WithdrawalTrimmed(WithdrawalTrimmedEvent {
+                    request_id,
+                    account_id: account_id.clone(),
+                    amount: U128(amount - excess),
+                    trimmed: U128(excess),
+                })
+                .emit();
+
+                excess = 0;
+            }
+        }
+    }
+
+    fn remove_scheduled_withdrawal(&mut self, position: usize) -> ScheduledWithdrawal {
+        let request = self.scheduled_withdrawals.remove(position);
+
+        if request.storage_deposit > 0 {
+            let _ = Promise::new(request.account_id.clone())
+                .transfer(NearToken::from_yoctonear(request.storage_deposit));
+        }
+
+        request
+    }
+
+    fn scheduled_amount(&self, account_id: &AccountId) -> u128 {
+        self.scheduled_withdrawals
+            .iter()
+            .filter(|request| &request.account_id == account_id)
+            .map(|request| request.amount)
+            .sum()
+    }
+
+    fn early_exit_penalty_bps(&self, lock_started_at: u64, now: u64) -> (u64, u128) {
+        let (start, _) = self.exit_window_at(lock_started_at, now);
+        let ns_until_window = start.saturating_sub(now);
+
+        let penalty_bps = EARLY_EXIT_MAX_PENALTY_BPS * ns_until_window as u128
+            / self.lock_duration_ns as u128;
+
+        (ns_until_window, penalty_bps.min(EARLY_EXIT_MAX_PENALTY_BPS))
+    }
+
+    // Consumes lots in order of cheapest penalty first, i.e. soonest window.
+    fn early_exit_plan(
+        &self,
+        participant: &Participant,
+        amount: u128,
+        now: u64,
+    ) -> Vec<EarlyExitLot> {
+        let mut candidates: Vec<(u64, u128, &PositionLot)> = participant
+            .lots
+            .iter()
+            .filter(|lot| lot.amount > 0)
+            .map(|lot| {
+                let (ns_until_window, penalty_bps) =
+                    self.early_exit_penalty_bps(lot.lock_started_at, now);
+                (ns_until_window, penalty_bps, lot)
+            })
+            .collect();
+
+        candidates.sort_by_key(|(ns_until_window, _, lot)| (*ns_until_window, lot.lot_id));
+
+        let mut remaining = amount;
+        let mut plan = Vec::new();
+
+        for (ns_until_window, penalty_bps, lot) in candidates {
+            if remaining == 0 {
+                break;
+            }
+
+            let take = lot.amount.min(remaining);
+            remaining -= take;
+
+            plan.push(EarlyExitLot {
+                lot_id: lot.lot_id,
+                amount: U128(take),
+                ns_until_window,
+                penalty_bps,
+                penalty: U128(take * penalty_bps / BPS_DENOMINATOR),
+            });
+        }
+
+        require!(remaining == 0, "Amount exceeds position lots");
+
+        plan
+    }
+
+    fn unlocked_amount(&self, participant: &Participant, now: u64) -> u128 {
+        participant
+            .lots
+            .iter()
+            .filter(|lot| self.is_lock_window_open(lot.lock_started_at, now))
+            .map(|lot| lot.amount)
+            .sum()
+    }
+
+    fn fold_state_effect(&mut self, effect: &StateEffect) {
+        let encoded = borsh::to_vec(effect).expect("Effect encoding failed");
+
+        let mut preimage = Vec::with_capacity(32 + encoded.len());
+        preimage.extend_from_slice(&self.state_hash);
+        preimage.extend_from_slice(&encoded);
+
+        self.state_hash = env::sha256_array(&preimage);
+        self.state_hash_length += 1;
+    }
+
+    fn participant_index(&self, account_id: &AccountId) -> Option<usize> {
+        self.participants
+            .iter()
+            .position(|p| &p.account_id == account_id)
+    }
+
+    fn eruption_index(&self, eruption_id: u64) -> Option<usize> {
+        self.eruptions
+            .iter()
+            .position(|e| e.eruption_id == eruption_id)
+    }
+
+    fn tpi_deployment_index(&self, deployment_id: u64) -> Option<usize> {
+        self.tpi_deployments
+            .iter()
+            .position(|d| d.deployment_id == deployment_id)
+    }
+
+    fn route_to_treasury_bucket(&mut self, amount: u128) {
+        self.tpi_treasury_routed = self
+            .tpi_treasury_routed
+            .checked_add(amount)
+            .expect("Treasury routed overflow");
+    }
+
+    fn tpi_closed_efficiencies(&self) -> Vec<u128> {
+        self.tpi_deployments
+            .iter()
+            .filter(|d| d.closed && !d.excluded_from_trend)
+            .map(|d| d.efficiency_bps)
+            .collect()
+    }
+
+    // OIM rebalancing is specified but has no runtime enforcement in this
+    // contract, so the status never reports it as live.
+    fn oim_status(&self) -> (&'static str, StatusCode) {
+        if self.pause_flags.any() {
+            ("SPECIFIED_PAUSED_BY_OWNER", StatusCode::Paused)
+        } else if !self.verification_flags.contract_source_verified {
+            ("SPECIFIED_PENDING_RUNTIME_VERIFICATION", StatusCode::Specified)
+        } else {
+            ("SPECIFIED_NOT_ENFORCED_BY_VERIFIED_SOURCE", StatusCode::Specified)
+        }
+    }
+
+    fn tpi_state(&self) -> TpiState {
+        let efficiencies = self.tpi_closed_efficiencies();
+
+        let recent_start = efficiencies.len().saturating_sub(TPI_TREND_WINDOW);
+        let recent = &efficiencies[recent_start..];
+        let previous = &efficiencies[recent_start.saturating_sub(TPI_TREND_WINDOW)..recent_start];
+
+        let average = |samples: &[u128]| -> Option<u128> {
+            if samples.is_empty() {
+                return None;
+            }
+            Some(samples.iter().sum::<u128>() / samples.len() as u128)
+        };
+
+        let average_efficiency_bps = average(recent);
+        let previous_average_efficiency_bps = average(previous);
+
+        let status = match average_efficiency_bps {
+            None => TpiStatus::Full,
+            Some(value) if value >= TPI_FULL_MIN_EFFICIENCY_BPS => TpiStatus::Full,
+            Some(value) if value >= TPI_REDUCED_MIN_EFFICIENCY_BPS => TpiStatus::Reduced,
+            Some(_) => TpiStatus::Frozen,
+        };
+
+        let trend_delta_bps = match (average_efficiency_bps, previous_average_efficiency_bps) {
+            (Some(current), Some(prior)) => current as i128 - prior as i128,
+            _ => 0,
+        };
+
+        let open_deployed: u128 = self
+            .tpi_deployments
+            .iter()
+            .filter(|d| !d.closed)
+            .map(|d| d.deployed_amount)
+            .sum();
+
+        let treasury_bucket = self
+            .tpi_treasury_routed
+            .saturating_add(self.tpi_total_returned)
+            .saturating_sub(self.tpi_total_deployed);
+
+        let max_deploy_bps = status.max_deploy_bps();
+        let deployment_power = treasury_bucket * max_deploy_bps / BPS_DENOMINATOR;
+
+        TpiState {
+            status,
+            trend_window: TPI_TREND_WINDOW as u64,
+            sample_count: recent.len() as u64,
+            average_efficiency_bps: average_efficiency_bps.unwrap_or(0),
+            previous_average_efficiency_bps,
+            trend_delta_bps,
+            treasury_bucket,
+            max_deploy_bps,
+            deployment_power,
+            open_deployed,
+        }
+    }
+
+    // The status is always derived from the closed samples; callers pass the
+    // status they saw before mutating so a transition can be announced.
+    fn emit_tpi_status_change(&self, previous: TpiStatus) {
+        let state = self.tpi_state();
+
+        if state.status == previous {
+            return;
+        }
+
+        VolcanoEvent::TpiStatusUpdated(TpiStatusUpdatedEvent {
+            previous,
+            status: state.status,
+            average_efficiency_bps: U128(state.average_efficiency_bps),
+            trend_delta_bps: state.trend_delta_bps as i64,
+            sample_count: state.sample_count,
+            max_deploy_bps: U128(state.max_deploy_bps),
+        })
+        .emit();
+
+        if self.legacy_logs_enabled {
+            env::log_str(&format!(
+                "TPI_STATUS_UPDATED previous={} status={} average_efficiency_bps={} trend_delta_bps={} sample_count={} max_deploy_bps={}",
+                previous.as_str(),
+                state.status.as_str(),
+                state.average_efficiency_bps,
+                state.trend_delta_bps,
+                state.sample_count,
+                state.max_deploy_bps
+            ));
+        }
+    }
+
+    fn allowlist_index(&self, account_id: &AccountId) -> Option<usize> {
+        self.allowlist
+            .iter()
+            .position(|e| &e.account_id == account_id)
+    }
+
+    fn total_locked_position(&self) -> u128 {
+        self.participants
+            .iter()
+            .map(|p| p.position_balance)
+            .sum()
+    }
+
+    fn has_pre_launch_access(&self, account_id: &AccountId) -> bool {
+        self.launch_stage == LaunchStage::Public || self.allowlist_index(account_id).is_some()
+    }
+
+    fn assert_pre_launch_access(&self, account_id: &AccountId) {
+        require!(
+            self.has_pre_launch_access(account_id),
+            "Account not allowlisted before public launch"
+        );
+    }
+
+    // Every path that adds to a locked position must go through this check;
+    // today that is only `deposit`, as there is no fungible-token path.
+    fn assert_deposit_capacity(&self, account_id: &AccountId, locked_position: u128) {
+        self.assert_pre_launch_access(account_id);
+
+        let capacity = self.deposit_capacity(account_id.clone());
+
+        if let Some(U128(remaining)) = capacity.account_remaining {
+            if locked_position > remaining {
+                env::panic_str(&format!(
+                    "Deposit exceeds per-account max position: locked_position={} remaining={}",
+                    locked_position, remaining
+                ));
+            }
+        }
+
+        if let Some(U128(remaining)) = capacity.tvl_remaining {
+            if locked_position > remaining {
+                env::panic_str(&format!(
+                    "Deposit exceeds global TVL cap: locked_position={} remaining={}",
+                    locked_position, remaining
+                ));
+            }
+        }
+    }
+
+    fn deposit_capacity(&self, account_id: AccountId) -> DepositCapacity {
+        let position_balance = self.get_position_balance(account_id.clone());
+        let total_locked_position = self.total_locked_position();
+
+        let entry = self
+            .allowlist_index(&account_id)
+            .map(|index| &self.allowlist[index]);
+
+        // Per-account caps only bind before public launch; the TVL cap binds whenever set.
+        let max_position = match self.launch_stage {
+            LaunchStage::Public => None,
+            _ => Some(entry.map_or(0, |e| e.max_position)),
+        };
+
+        let account_remaining = max_position.map(|max| max.saturating_sub(position_balance));
+        let tvl_remaining = self
+            .tvl_cap
+            .map(|cap| cap.saturating_sub(total_locked_position));
+
+        let max_locked_position_addable = match (account_remaining, tvl_remaining) {
+            (Some(a), Some(t)) => Some(a.min(t)),
+            (Some(a), None) => Some(a),
+            (None, Some(t)) => Some(t),
+            (None, None) => None,
+        };
+
+        let max_gross_deposit = max_locked_position_addable.map(|locked| {
+            locked
+                .saturating_mul(BPS_DENOMINATOR)
+                / (BPS_DENOMINATOR - SYSTEM_FEE_BPS)
+        });
+
+        DepositCapacity {
+            account_id,
+            launch_stage: self.launch_stage,
+            allowlisted: entry.is_some(),
+            position_balance: U128(position_balance),
+            max_position: max_position.map(U128),
+            account_remaining: account_remaining.map(U128),
+            total_locked_position: U128(total_locked_position),
+            tvl_cap: self.tvl_cap.map(U128),
+            tvl_remaining: tvl_remaining.map(U128),
+            max_locked_position_addable: max_locked_position_addable.map(U128),
+            max_gross_deposit: max_gross_deposit.map(U128),
+        }
+    }
+
+    fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            "Only owner"
+        );
+    }
+
+    fn assert_treasury_authority(&self) {
+        let caller = env::predecessor_account_id();
+
+        require!(
+            caller == self.owner_id || caller == self.treasury_wallet,
+            "Only owner or treasury wallet"
+        );
+    }
+
+    fn eligible_accounts(&self) -> Vec<AccountId> {
+        let now = env::block_timestamp();
+
+        self.participants
+            .iter()
+            .filter(|p| self.eligible_position(p, now).is_some())
+            .map(|p| p.account_id.clone())
+            .collect()
+    }
+
+    // Position held as of `as_of`: lots opened later do not count.
+    // Returns the eligible position, which is the distribution weight in
+    // position-weighted mode. Lots double as holding checkpoints: only amounts
+    // held for at least `min_holding_age_ns` before `as_of` count, so a deposit
+    // made just before (or crossing) a milestone earns nothing from it.
+    fn eligible_position(&self, participant: &Participant, as_of: u64) -> Option<u128> {
+        let held: u128 = participant
+            .lots
+            .iter()
+            .filter(|lot| lot.lock_started_at.saturating_add(self.min_holding_age_ns) <= as_of)
+            .map(|lot| lot.amount)
+            .sum();
+
+        if participant.active
+            && held >= MIN_ELIGIBLE_POSITION_YOCTO
+            && self.passes_eligibility_policy(participant, as_of)
+        {
+            Some(held)
+        } else {
+            None
+        }
+    }
+
+    fn passes_eligibility_policy(&self, participant: &Participant, as_of: u64) -> bool {
+        let policy = &self.eligibility_policy;
+
+        if participant.created_at.saturating_add(policy.min_account_age_ns) > as_of {
+            return false;
+        }
+
+        if !policy.attestation_required {
+            return true;
+        }
+
+        // Attestations signed by a rotated-out key no longer count.
+        self.eligibility_attestations.iter().any(|a| {
+            a.account_id == participant.account_id
+                && Some(&a.attester_public_key) == policy.attester_public_key.as_ref()
+                && a.submitted_at <= as_of
+                && a.expires_at >= as_of
+        })
+    }
+
+    fn attestation_message(&self, account_id: &AccountId, expires_at: u64) -> Vec<u8> {
+        format!(
+            "near_intersect_volcano:eligibility:{}:{}:{}",
+            env::current_account_id(),
+            account_id,
+            expires_at
+        )
+        .into_bytes()
+    }
+
+    fn is_exit_window_open_for(&self, participant: &Participant, now: u64) -> bool {
+        if !participant.active {
+            return false;
+        }
+
+        participant
+            .lots
+            .iter()
+            .any(|lot| self.is_lock_window_open(lot.lock_started_at, now))
+    }
+
+    fn is_lock_window_open(&self, lock_started_at: u64, now: u64) -> bool {
+        if now < lock_started_at {
+            return false;
+        }
+
+        let elapsed = now - lock_started_at;
+
+        if elapsed < self.lock_duration_ns {
+            return false;
+        }
+
+        let cycle = self
+            .lock_duration_ns
+            .checked_add(self.exit_window_ns)
+            .expect("Cycle overflow");
+
+        let offset = (elapsed - self.lock_duration_ns) % cycle;
+
+        offset < self.exit_window_ns
+    }
+
+    // Returns the window that is open at `now`, or the next one to open.
+    fn exit_window_at(&self, lock_started_at: u64, now: u64) -> (u64, u64) {
+        let first_start = lock_started_at
+            .checked_add(self.lock_duration_ns)
+            .expect("Window start overflow");
+
+        if now < first_start {
+            return (first_start, first_start + self.exit_window_ns);
+        }
+
+        let cycle = self
+            .lock_duration_ns
+            .checked_add(self.exit_window_ns)
+            .expect("Cycle overflow");
+
+        let cycles_elapsed = (now - first_start) / cycle;
+        let mut start = first_start + cycles_elapsed * cycle;
+
+        if now >= start + self.exit_window_ns {
+            start += cycle;
+        }
+
+        (start, start + self.exit_window_ns)
+    }
+
+    fn exit_schedule(&self, account_id: AccountId, count: u64, now: u64) -> ExitSchedule {
+        let count = count.min(MAX_EXIT_SCHEDULE_COUNT) as usize;
+        let cycle = self
+            .lock_duration_ns
+            .checked_add(self.exit_window_ns)
+            .expect("Cycle overflow");
+
+        let mut windows: Vec<ExitWindow> = Vec::new();
+        let mut exit_window_open = false;
+
+        if let Some(index) = self.participant_index(&account_id) {
+            let participant = &self.participants[index];
+            exit_window_open = self.is_exit_window_open_for(participant, now);
+
+            for lot in participant.lots.iter().filter(|lot| lot.amount > 0) {
+                let (mut start, _) = self.exit_window_at(lot.lock_started_at, now);
+
+                for _ in 0..count {
+                    match windows.iter_mut().find(|w| w.start == start) {
+                        Some(window) => {
+                            window.lot_ids.push(lot.lot_id);
+                            window.amount.0 += lot.amount;
+                        }
+                        None => windows.push(ExitWindow {
+                            start,
+                            end: start + self.exit_window_ns,
+                            lot_ids: vec![lot.lot_id],
+                            amount: U128(lot.amount),
+                        }),
+                    }
+
+                    start += cycle;
+                }
+            }
+        }
+
+        windows.sort_by_key(|w| w.start);
+        windows.truncate(count);
+
+        let ns_until_next_opening = windows
+            .iter()
+            .find(|w| w.start > now)
+            .map(|w| w.start - now);
+
+        let ns_until_current_window_closes = windows
+            .iter()
+            .filter(|w| w.start <= now && now < w.end)
+            .map(|w| w.end - now)
+            .max();
+
+        ExitSchedule {
+            account_id,
+            now,
+            exit_window_open,
+            ns_until_next_opening,
+            ns_until_current_window_closes,
+            windows,
+        }
+    }
+
+    fn phase_threshold_by_index(&self, phase_index: usize) -> Option<u128> {
+        if phase_index < self.phase_milestones.len() {
+            return Some(self.phase_milestones[phase_index]);
+        }
+
+        let continuation = self.phase_continuation?;
+        let steps = (phase_index + 1 - self.phase_milestones.len()) as u64;
+        if continuation.max_phases.is_some_and(|max| steps > max) {
+            return None;
+        }
+
+        // Stops where the next threshold would no longer fit in a u128.
+        let ceiling = mul_div(u128::MAX, BPS_DENOMINATOR, continuation.ratio_bps);
+        let mut threshold = *self.phase_milestones.last()?;
+        for _ in 0..steps {
+            if threshold > ceiling {
+                return None;
+            }
+            threshold = mul_div(threshold, continuation.ratio_bps, BPS_DENOMINATOR);
+        }
+
+        Some(threshold)
+    }
+
+    fn projected_eligible_positions(&self) -> Vec<u128> {
+        let (as_of, participant_count) = match &self.pending_eruption {
+            Some(pending) => (pending.as_of, pending.participant_count as usize),
+            None => (env::block_timestamp(), self.participants.len()),
+        };
+
+        self.participants[..participant_count]
+            .iter()
+            .filter_map(|p| self.eligible_position(p, as_of))
+            .collect()
+    }
+
+    // (share_per_wallet, allocated) with the same rounding as a real snapshot.
+    fn projected_allocation(&self, pool: u128, positions: &[u128]) -> (u128, u128) {
+        match self.distribution_mode {
+            DistributionMode::EqualShare if positions.is_empty() => (0, 0),
+            DistributionMode::EqualShare => {
+                let share = pool / positions.len() as u128;
+                (share, share * positions.len() as u128)
+            }
+            DistributionMode::PositionWeighted => {
+                let total: u128 = positions.iter().sum();
+                let allocated = positions
+                    .iter()
+                    .map(|position| mul_div(pool, *position, total))
+                    .sum();
+                (0, allocated)
+            }
+        }
+    }
+
+    // Appended phases must not have started: with a pending scan or a phase
+    // already reached through the continuation, the table is frozen there.
+    fn assert_phase_milestones_appendable(&self, milestones: &[u128]) {
+        require!(!milestones.is_empty(), "No phase milestones");
+
+        let first_new_index = self.phase_milestones.len();
+        let started = self.eruption_count as usize + usize::from(self.pending_eruption.is_some());
+        require!(
+            started <= first_new_index,
+            "Phase milestones can only be appended for phases not yet started"
+        );
+
+        let last = *self.phase_milestones.last().expect("Phase table empty");
+        assert_increasing_milestones(last, milestones);
+    }
+
+    pub fn get_system_status(&self) -> SystemStatus {
+        let flags = self.verification_flags;
+        let pauses = self.pause_flags;
+        let audited = !self.audit_attestations.is_empty();
+        let tpi = self.tpi_state().status;
+
+        let (project_status, project_code) = match self.launch_stage {
+            LaunchStage::Development => ("DEVELOPMENT_STAGE", StatusCode::Development),
+            LaunchStage::Audited => ("AUDITED_STAGE", StatusCode::Audited),
+            LaunchStage::Public => ("PUBLIC_STAGE", StatusCode::Public),
+        };
+
+        let (website_status, website_code) = if flags.website_public {
+            ("PUBLIC", StatusCode::Public)
+        } else {
+            ("PENDING", StatusCode::Pending)
+        };
+
+        let (wallet_status, wallet_code) = if flags.wallet_connection_verified {
+            ("VERIFIED", StatusCode::Verified)
+        } else {
+            ("PENDING_UNTIL_VERIFIED", StatusCode::Pending)
+        };
+
+        let (contract_status, contract_code) = if pauses.any() {
+            ("PAUSED_BY_OWNER", StatusCode::Paused)
+        } else if !flags.contract_source_verified {
+            ("SOURCE_PRESENT_NOT_PUBLIC_FUND_LAUNCH_VERIFIED", StatusCode::Pending)
+        } else if self.launch_stage == LaunchStage::Public {
+            ("SOURCE_VERIFIED_PUBLIC", StatusCode::Public)
+        } else {
+            ("SOURCE_VERIFIED_NOT_PUBLIC_FUND_LAUNCH", StatusCode::Verified)
+        };
+
+        let (treasury_status, treasury_code) = if !flags.treasury_wallets_verified {
+            ("PENDING_PUBLIC_VERIFICATION", StatusCode::Pending)
+        } else if tpi == TpiStatus::Frozen {
+            ("VERIFIED_DEPLOYMENT_FROZEN", StatusCode::Frozen)
+        } else {
+            ("VERIFIED", StatusCode::Verified)
+        };
+
+        let (eruption_engine_status, eruption_engine_code) = if pauses.claims {
+            ("CLAIMS_PAUSED_BY_OWNER", StatusCode::Paused)
+        } else if self.get_next_eruption_threshold() == 0 {
+            ("PHASE_TABLE_EXHAUSTED", StatusCode::Exhausted)
+        } else if audited {
+            ("IMPLEMENTED_AS_SNAPSHOT_CLAIM_MODEL_AUDITED", StatusCode::Audited)
+        } else {
+            ("IMPLEMENTED_AS_SNAPSHOT_CLAIM_MODEL_PENDING_AUDIT", StatusCode::Implemented)
+        };
+
+        let (oim_status, oim_code) = self.oim_status();
+
+        let tpi_code = match tpi {
+            TpiStatus::Full => StatusCode::Full,
+            TpiStatus::Reduced => StatusCode::Reduced,
+            TpiStatus::Frozen => StatusCode::Frozen,
+        };
+
+        let (public_fund_launch_status, public_fund_launch_code) = match self.launch_stage {
+            LaunchStage::Public => ("APPROVED_PUBLIC_FUND_LAUNCH", StatusCode::Approved),
+            LaunchStage::Audited => (
+                "NOT_APPROVED_AUDITED_PENDING_PUBLIC_VERIFICATION",
+                StatusCode::NotApproved,
+            ),
+            LaunchStage::Development => (
+                "NOT_APPROVED_BEFORE_AUDIT_SOURCE_WASM_CONTRACT_AND_LEGAL_VERIFICATION",
+                StatusCode::NotApproved,
+            ),
+        };
+
+        SystemStatus {
+            project_status: project_status.to_string(),
+            website_status: website_status.to_string(),
+            wallet_status: wallet_status.to_string(),
+            contract_status: contract_status.to_string(),
+            treasury_status: treasury_status.to_string(),
+            eruption_engine_status: eruption_engine_status.to_string(),
+            oim_status: oim_status.to_string(),
+            tpi_status: self.get_tpi_status(),
+            public_fund_launch_status: public_fund_launch_status.to_string(),
+            codes: SystemStatusCodes {
+                project_status: project_code,
+                website_status: website_code,
+                wallet_status: wallet_code,
+                contract_status: contract_code,
+                treasury_status: treasury_code,
+                eruption_engine_status: eruption_engine_code,
+                oim_status: oim_code,
+                tpi_status: tpi_code,
+                public_fund_launch_status: public_fund_launch_code,
+            },
+        }
+    }
+
+    pub fn get_launch_stage(&self) -> LaunchStage {
+        self.launch_stage
+    }
+
+    pub fn get_launch_artifacts(&self) -> LaunchArtifacts {
+        self.launch_artifacts.clone()
+    }
+
+    pub fn get_allowlist(&self) -> Vec<AllowlistEntry> {
+        self.allowlist.clone()
+    }
+
+    pub fn is_allowlisted(&self, account_id: AccountId) -> bool {
+        self.allowlist_index(&account_id).is_some()
+    }
+
+    pub fn get_tvl_cap(&self) -> Option<u128> {
+        self.tvl_cap
+    }
+
+    pub fn get_total_locked_position(&self) -> u128 {
+        self.total_locked_position()
+    }
+
+    pub fn get_deposit_capacity(&self, account_id: AccountId) -> DepositCapacity {
+        self.deposit_capacity(account_id)
+    }
+
+    pub fn get_state_hash_chain(&self) -> StateHashChain {
+        StateHashChain {
+            head: to_hex(&self.state_hash),
+            length: self.state_hash_length,
+        }
+    }
+
+    pub fn get_legacy_logs_enabled(&self) -> bool {
+        self.legacy_logs_enabled
+    }
+
+    pub fn get_pause_flags(&self) -> PauseFlags {
+        self.pause_flags
+    }
+
+    pub fn get_verification_flags(&self) -> VerificationFlags {
+        self.verification_flags
+    }
+
+    pub fn get_audit_attestations(&self) -> Vec<AuditAttestation> {
+        self.audit_attestations.clone()
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_operations_wallet(&self) -> AccountId {
+        self.operations_wallet.clone()
+    }
+
+    pub fn get_treasury_wallet(&self) -> AccountId {
+        self.treasury_wallet.clone()
+    }
+
+    pub fn get_growth_wallet(&self) -> AccountId {
+        self.growth_wallet.clone()
+    }
+
+    pub fn get_reserve_wallet(&self) -> AccountId {
+        self.reserve_wallet.clone()
+    }
+
+    pub fn get_test_mode(&self) -> bool {
+        self.test_mode
+    }
+
+    pub fn get_lock_duration_ns(&self) -> u64 {
+        self.lock_duration_ns
+    }
+
+    pub fn get_exit_window_ns(&self) -> u64 {
+        self.exit_window_ns
+    }
+
+    pub fn get_system_fee_bps(&self) -> u128 {
+        SYSTEM_FEE_BPS
+    }
+
+    pub fn get_fee_split_bps(&self) -> (u128, u128, u128, u128, u128) {
+        (
+            TREASURY_BPS,
+            GROWTH_BPS,
+            VOLCANO_BPS,
+            RESERVE_BPS,
+            CORE_OPS_BPS,
+        )
+    }
+
+    pub fn get_volcano_pressure(&self) -> u128 {
+        self.volcano_pressure
+    }
+
+    pub fn get_next_eruption_threshold(&self) -> u128 {
+        self.phase_threshold_by_index(self.eruption_count as usize)
+            .unwrap_or_default()
+    }
+
+    // Projects the next eruption as if its milestone were crossed now, or at
+    // the frozen `as_of` of a scan already in progress.
+    pub fn simulate_next_eruption(&self) -> EruptionProjection {
+        let phase_number = self.eruption_count + 1;
+        let threshold = self.phase_threshold_by_index(self.eruption_count as usize);
+        let positions = self.projected_eligible_positions();
+
+        let distribution_pool = threshold.map_or(0, |t| distribution_pool_for(phase_number, t));
+        let (share_per_wallet, allocated) =
+            self.projected_allocation(distribution_pool, &positions);
+
+        EruptionProjection {
+            phase_number,
+            threshold: threshold.map(U128),
+            pressure: U128(self.volcano_pressure),
+            shortfall: U128(threshold.map_or(0, |t| t.saturating_sub(self.volcano_pressure))),
+            distribution_pool: U128(distribution_pool),
+            retained: U128(threshold.map_or(0, |t| t - distribution_pool)),
+            distribution_mode: self.distribution_mode,
+            eligible_count: positions.len() as u64,
+            total_eligible_position: U128(positions.iter().sum()),
+            share_per_wallet: U128(share_per_wallet),
+            unallocated_remainder: U128(distribution_pool - allocated),
+            scan_in_progress: self.pending_eruption.is_some(),
+        }
+    }
+
+    // Chained eruptions assume today's eligible set for every phase; the
+    // hypothetical depositor is never part of it, having no aged lots yet.
+    // The eruption figures are projected even when the deposit would be
+    // rejected, so callers must check `deposit_allowed`. Without an account only
+    // the TVL cap is checked, and pre-launch quotes are rejected outright.
+    pub fn quote_deposit_impact(
+        &self,
+        account_id: Option<AccountId>,
+        amount: u128,
+    ) -> DepositImpactQuote {
+        // Same per-bucket rounding as `credit_deposit`; mul_div cannot overflow
+        // here since every bucket is below the amount.
+        let fee: u128 = [TREASURY_BPS, GROWTH_BPS, VOLCANO_BPS, RESERVE_BPS, CORE_OPS_BPS]
+            .iter()
+            .map(|bps| mul_div(amount, *bps, BPS_DENOMINATOR))
+            .sum();
+        let volcano = mul_div(amount, VOLCANO_BPS, BPS_DENOMINATOR);
+        let locked_position = amount - fee;
+
+        let (account_remaining, tvl_remaining, max_locked_position_addable) = match &account_id {
+            Some(account_id) => {
+                let capacity = self.deposit_capacity(account_id.clone());
+                (
+                    capacity.account_remaining,
+                    capacity.tvl_remaining,
+                    capacity.max_locked_position_addable,
+                )
+            }
+            None => {
+                let tvl_remaining = self
+                    .tvl_cap
+                    .map(|cap| U128(cap.saturating_sub(self.total_locked_position())));
+                (None, tvl_remaining, tvl_remaining)
+            }
+        };
+
+        let pressure_overflows = self.volcano_pressure.checked_add(volcano).is_none();
+        let pressure_after = self.volcano_pressure.saturating_add(volcano);
+
+        let rejection_reason = if self.pause_flags.deposits {
+            Some("Deposits paused")
+        } else if amount == 0 {
+            Some("Attach deposit")
+        } else if account_id.is_none() && self.launch_stage != LaunchStage::Public {
+            Some("Allowlisted account required before public launch")
+        } else if account_id
+            .as_ref()
+            .is_some_and(|account_id| !self.has_pre_launch_access(account_id))
+        {
+            Some("Account not allowlisted before public launch")
+        } else if account_remaining.is_some_and(|r| locked_position > r.0) {
+            Some("Deposit exceeds per-account max position")
+        } else if tvl_remaining.is_some_and(|r| locked_position > r.0) {
+            Some("Deposit exceeds global TVL cap")
+        } else if pressure_overflows {
+            Some("Deposit would overflow volcano pressure")
+        } else {
+            None
+        };
+
+        let next_threshold = self.phase_threshold_by_index(self.eruption_count as usize);
+        let positions = self.projected_eligible_positions();
+
+        let mut pressure = pressure_after;
+        let mut phase_index = self.eruption_count as usize;
+        let mut eruptions_triggered = 0;
+
+        while let Some(threshold) = self.phase_threshold_by_index(phase_index) {
+            if pressure < threshold {
+                break;
+            }
+
+            let pool = distribution_pool_for(phase_index as u64 + 1, threshold);
+            let (_, allocated) = self.projected_allocation(pool, &positions);
+
+            // Retained share, remainder and overshoot all stay as pressure.
+            pressure -= allocated;
+            phase_index += 1;
+            eruptions_triggered += 1;
+        }
+
+        DepositImpactQuote {
+            account_id,
+            amount: U128(amount),
+            fee: U128(fee),
+            locked_position: U128(locked_position),
+            pressure_added: U128(volcano),
+            pressure_before: U128(self.volcano_pressure),
+            pressure_after: U128(pressure_after),
+            next_threshold: next_threshold.map(U128),
+            shortfall_after: U128(next_threshold.map_or(0, |t| t.saturating_sub(pressure_after))),
+            triggers_eruption: eruptions_triggered > 0,
+            eruptions_triggered,
+            pressure_after_eruptions: U128(pressure),
+            deposit_allowed: rejection_reason.is_none(),
+            rejection_reason: rejection_reason.map(str::to_string),
+            max_locked_position_addable,
+        }
+    }
+
+    pub fn get_current_phase_number(&self) -> u64 {
+        self.eruption_count + 1
+    }
+
+    pub fn get_phase_milestones(&self) -> Vec<u128> {
+        self.phase_milestones.clone()
+    }
+
+    pub fn get_phase_rule(&self) -> PhaseRule {
+        PhaseRule {
+            milestones: self.phase_milestones.clone(),
+            continuation: self.phase_continuation,
+            governance_timelock_ns: self.governance_timelock_ns,
+            pending_proposal: self.pending_phase_proposal.clone(),
+        }
+    }
+
+    // `None` once neither the table nor the continuation defines the phase.
+    pub fn get_phase_threshold(&self, phase_number: u64) -> Option<u128> {
+        require!(phase_number > 0, "Phase numbers start at 1");
+        self.phase_threshold_by_index((phase_number - 1) as usize)
+    }
+
+    pub fn get_eruption_count(&self) -> u64 {
+        self.eruption_count
+    }
+
+    pub fn get_created_record_count(&self) -> u64 {
+        self.created_record_count
+    }
+
+    pub fn get_participant_count(&self) -> u64 {
+        self.participants.len() as u64
+    }
+
+    pub fn get_eligible_participant_count(&self) -> u64 {
+        self.eligible_accounts().len() as u64
+    }
+
+    pub fn get_participants(&self) -> Vec<Participant> {
+        self.participants.clone()
+    }
+
+    pub fn get_participant(&self, account_id: AccountId) -> Option<Participant> {
+        self.participant_index(&account_id)
+            .map(|index| self.participants[index].clone())
+    }
+
+    pub fn get_position_balance(&self, account_id: AccountId) -> u128 {
+        self.participant_index(&account_id)
+            .map(|index| self.participants[index].position_balance)
+            .unwrap_or(0)
+    }
+
+    pub fn get_position_lots(&self, account_id: AccountId) -> Vec<PositionLotView> {
+        let now = env::block_timestamp();
+
+        match self.participant_index(&account_id) {
+            Some(index) => self.participants[index]
+                .lots
+                .iter()
+                .map(|lot| {
+                    let (start, end) = self.exit_window_at(lot.lock_started_at, now);
+
+                    PositionLotView {
+                        lot_id: lot.lot_id,
+                        amount: U128(lot.amount),
+                        original_amount: U128(lot.original_amount),
+                        lock_started_at: lot.lock_started_at,
+                        exit_window_open: self.is_lock_window_open(lot.lock_started_at, now),
+                        next_exit_window_start: start,
+                        next_exit_window_end: end,
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn get_exit_schedule(&self, account_id: AccountId, count: u64) -> ExitSchedule {
+        self.exit_schedule(account_id, count, env::block_timestamp())
+    }
+
+    pub fn quote_early_exit(&self, account_id: AccountId, amount: u128) -> EarlyExitQuote {
+        let index = self
+            .participant_index(&account_id)
+            .expect("Participant not found");
+
+        let participant = &self.participants[index];
+        require!(
+            participant.position_balance >= amount,
+            "Insufficient position balance"
+        );
+
+        let lots = self.early_exit_plan(participant, amount, env::block_timestamp());
+        let penalty: u128 = lots.iter().map(|lot| lot.penalty.0).sum();
+
+        let fee = amount * TREASURY_BPS / BPS_DENOMINATOR
+            + amount * GROWTH_BPS / BPS_DENOMINATOR
+            + amount * VOLCANO_BPS / BPS_DENOMINATOR
+            + amount * RESERVE_BPS / BPS_DENOMINATOR
+            + amount * CORE_OPS_BPS / BPS_DENOMINATOR;
+
+        EarlyExitQuote {
+            account_id,
+            gross: U128(amount),
+            fee: U128(fee),
+            penalty: U128(penalty),
+            net_to_user: U128(amount - fee - penalty),
+            lots,
+        }
+    }
+
+    pub fn get_scheduled_withdrawals(&self, account_id: AccountId) -> Vec<ScheduledWithdrawal> {
+        self.scheduled_withdrawals
+            .iter()
+            .filter(|request| request.account_id == account_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_inheritance_status(&self, account_id: AccountId) -> Option<InheritanceStatus> {
+        let participant = &self.participants[self.participant_index(&account_id)?];
+
+        Some(InheritanceStatus {
+            account_id,
+            beneficiary_id: participant.beneficiary_id.clone(),
+            inactivity_period_ns: participant.inactivity_period_ns,
+            last_active_at: participant.last_active_at,
+            inactive_from: participant.beneficiary_id.as_ref().map(|_| {
+                participant
+                    .last_active_at
+                    .saturating_add(participant.inactivity_period_ns)
+            }),
+            challenge_ns: self.inheritance_challenge_ns,
+            inheritance_claim: participant.inheritance_claim.clone(),
+        })
+    }
+
+    pub fn get_claim_agents(&self, account_id: AccountId) -> Vec<AccountId> {
+        self.participant_index(&account_id)
+            .map(|index| self.participants[index].claim_agents.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn get_auto_compound(&self, account_id: AccountId) -> bool {
+        self.participant_index(&account_id)
+            .is_some_and(|index| self.participants[index].auto_compound)
+    }
+
+    pub fn get_claimable(&self, account_id: AccountId) -> Claimable {
+        let eruptions: Vec<ClaimableEruption> = self
+            .claimable_eruptions(&account_id, env::block_timestamp())
+            .into_iter()
+            .map(|(index, share)| ClaimableEruption {
+                eruption_id: self.eruptions[index].eruption_id,
+                share: U128(share),
+                claim_deadline: self.eruptions[index].claim_deadline,
+            })
+            .collect();
+
+        let total = eruptions.iter().map(|e| e.share.0).sum();
+
+        Claimable {
+            account_id,
+            eruptions,
+            total: U128(total),
+        }
+    }
+
+    pub fn get_eligibility_policy(&self) -> EligibilityPolicy {
+        self.eligibility_policy.clone()
+    }
+
+    pub fn get_eligibility_attestation(
+        &self,
+        account_id: AccountId,
+    ) -> Option<EligibilityAttestation> {
+        self.eligibility_attestations
+            .iter()
+            .find(|a| a.account_id == account_id)
+            .cloned()
+    }
+
+    // The exact UTF-8 string the attester signs.
+    pub fn get_attestation_message(&self, account_id: AccountId, expires_at: u64) -> String {
+        String::from_utf8(self.attestation_message(&account_id, expires_at))
+            .expect("Attestation message is UTF-8")
+    }
+
+    pub fn get_claim_window_ns(&self) -> u64 {
+        self.claim_window_ns
+    }
+
+    pub fn get_min_holding_age_ns(&self) -> u64 {
+        self.min_holding_age_ns
+    }
+
+    pub fn get_eligible_position(&self, account_id: AccountId) -> u128 {
+        self.participant_index(&account_id)
+            .and_then(|index| {
+                self.eligible_position(&self.participants[index], env::block_timestamp())
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn get_distribution_mode(&self) -> DistributionMode {
+        self.distribution_mode
+    }
+
+    pub fn get_pending_eruption(&self) -> Option<PendingEruption> {
+        self.pending_eruption.clone()
+    }
+
+    pub fn get_keeper_budget(&self) -> u128 {
+        self.keeper_budget
+    }
+
+    pub fn get_crank_work(&self) -> CrankWork {
+        let now = env::block_timestamp();
+
+        let eruption_pending = self.pending_eruption.is_some()
+            || self
+                .phase_threshold_by_index(self.eruption_count as usize)
+                .map(|threshold| self.volcano_pressure >= threshold)
+                .unwrap_or(false);
+
+        CrankWork {
+            sweepable_eruptions: self
+                .eruptions
+                .iter()
+                .filter(|eruption| Self::is_sweepable(eruption, now))
+                .count() as u64,
+            actionable_withdrawals: self
+                .scheduled_withdrawals
+                .iter()
+                .filter(|request| self.is_withdrawal_actionable(request, now))
+                .count() as u64,
+            eruption_pending,
+            keeper_budget: U128(self.keeper_budget),
+        }
+    }
+
+    pub fn get_scheduled_withdrawal_count(&self) -> u64 {
+        self.scheduled_withdrawals.len() as u64
+    }
+
+    pub fn get_early_exit_max_penalty_bps(&self) -> u128 {
+        EARLY_EXIT_MAX_PENALTY_BPS
+    }
+
+    pub fn get_unlocked_amount(&self, account_id: AccountId) -> u128 {
+        match self.participant_index(&account_id) {
+            Some(index) => {
+                self.unlocked_amount(&self.participants[index], env::block_timestamp())
+            }
+            None => 0,
+        }
+    }
+
+    pub fn is_exit_window_open(&self, account_id: AccountId) -> bool {
+        match self.participant_index(&account_id) {
+            Some(index) => self.is_exit_window_open_for(
+                &self.participants[index],
+                env::block_timestamp(),
+            ),
+            None => false,
+        }
+    }
+
+    pub fn get_eruption_snapshots(&self) -> Vec<EruptionSnapshot> {
+        self.eruptions.clone()
+    }
+
+    pub fn get_eruption_snapshot(&self, eruption_id: u64) -> Option<EruptionSnapshot> {
+        self.eruption_index(eruption_id)
+            .map(|index| self.eruptions[index].clone())
+    }
+
+    pub fn has_claimed_eruption(&self, account_id: AccountId, eruption_id: u64) -> bool {
+        match self.participant_index(&account_id) {
+            Some(index) => self.participants[index]
+                .claimed_eruption_ids
+                .contains(&eruption_id),
+            None => false,
+        }
+    }
+
+    pub fn get_create_record_fee_yocto(&self) -> u128 {
+        CREATE_RECORD_FEE_YOCTO
+    }
+
+    pub fn get_min_eligible_position_yocto(&self) -> u128 {
+        MIN_ELIGIBLE_POSITION_YOCTO
+    }
+
+    pub fn get_oim_status(&self) -> String {
+        self.oim_status().0.to_string()
+    }
+
+    pub fn get_tpi_status(&self) -> String {
+        format!("RUNTIME_{}", self.tpi_state().status.as_str())
+    }
+
+    pub fn get_tpi_deployments(&self) -> Vec<TpiDeployment> {
+        self.tpi_deployments.clone()
+    }
+
+    pub fn get_tpi_deployment(&self, deployment_id: u64) -> Option<TpiDeployment> {
+        self.tpi_deployment_index(deployment_id)
+            .map(|index| self.tpi_deployments[index].clone())
+    }
+
+    pub fn get_tpi_deployment_count(&self) -> u64 {
+        self.tpi_deployments.len() as u64
+    }
+
+    pub fn get_tpi_totals(&self) -> (u128, u128) {
+        (self.tpi_total_deployed, self.tpi_total_returned)
+    }
+
+    pub fn get_tpi_state(&self) -> TpiState {
+        self.tpi_state()
+    }
+
+    pub fn get_tpi_deployment_power(&self) -> u128 {
+        self.tpi_state().deployment_power
+    }
+
+    pub fn canonical_law_summary(&self) -> String {
+        "NEAR_INTERSECT_VOLCANO_CANONICAL_PRE_LAUNCH_LAW: development_stage; 5_percent_fee; treasury_1_40_growth_0_90_volcano_1_05_reserve_0_55_core_ops_1_10; production_lock_2_years; exit_window_7_days; test_mode_2_hours_7_minutes; no_manual_eruption; explicit_phase_table; phase_1_75_25; phase_2_plus_60_40; eligible_active_wallet_snapshot_claims; local_profiles_no_financial_rights; OIM_TPI_pending_runtime_verification; no_guaranteed_returns".to_string()
+    }
+}
+
+// Phase 1 distributes 75% of its threshold, every later phase 60%.
+fn distribution_pool_for(phase_number: u64, threshold: u128) -> u128 {
+    if phase_number == 1 {
+        threshold * 75 / 100
+    } else {
+        threshold * 60 / 100
+    }
+}
+
+fn assert_increasing_milestones(previous: u128, milestones: &[u128]) {
+    let mut previous = previous;
+    for &milestone in milestones {
+        require!(milestone > previous, "Phase milestones must strictly increase");
+        previous = milestone;
+    }
+}
+
+// floor(a * b / denominator) through a 256-bit intermediate; the caller
+// guarantees the result fits in a u128.
+fn mul_div(a: u128, b: u128, denominator: u128) -> u128 {
+    const MASK: u128 = u64::MAX as u128;
+
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+
+    let ll = a0 * b0;
+    let lh = a0 * b1;
+    let hl = a1 * b0;
+    let hh = a1 * b1;
+
+    let mid = (ll >> 64) + (lh & MASK) + (hl & MASK);
+    let lo = (ll & MASK) | ((mid & MASK) << 64);
+    let hi = hh + (lh >> 64) + (hl >> 64) + (mid >> 64);
+
+    let mut remainder: u128 = 0;
+    let mut quotient: u128 = 0;
+
+    for bit in (0..256).rev() {
+        let next = if bit >= 128 {
+            (hi >> (bit - 128)) & 1
+        } else {
+            (lo >> bit) & 1
+        };
+
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | next;
+        quotient <<= 1;
+
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+
+    quotient
+}
+
+fn is_lower_hex(value: &str, len: usize) -> bool {
+    value.len() == len
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.push(DIGITS[(byte >> 4) as usize] as char);
+        out.push(DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const MINUTE_NS: u64 = 60 * SECONDS_TO_NANOS;
+    const START_NS: u64 = 1_000 * SECONDS_TO_NANOS;
+    const AGED_NS: u64 = START_NS + TEST_MIN_HOLDING_AGE_NS;
+    const SCHEDULE_STORAGE_YOCTO: u128 = YOCTO_PER_NEAR / 100;
+
+    fn context(predecessor: AccountId, deposit: u128, now: u64) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id("volcano.near".parse().unwrap())
+            .predecessor_account_id(predecessor)
+            .attached_deposit(NearToken::from_yoctonear(deposit))
+            .account_balance(NearToken::from_near(1_000_000))
+            .block_timestamp(now)
+            .build());
+    }
+
+    fn setup() -> Contract {
+        setup_with_phase_table(None)
+    }
+
+    fn setup_with_deposit() -> Contract {
+        let mut contract = setup();
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+        contract
+    }
+
+    fn setup_with_phase_table(phase_table: Option<PhaseTableConfig>) -> Contract {
+        context(accounts(0), 0, START_NS);
+
+        let mut contract = Contract::new(
+            accounts(0),
+            "treasury.near".parse().unwrap(),
+            "growth.near".parse().unwrap(),
+            "reserve.near".parse().unwrap(),
+            true,
+            None,
+            phase_table,
+        );
+
+        contract.add_to_allowlist(accounts(1), Some(100 * YOCTO_PER_NEAR));
+        contract
+    }
+
+    fn deposit(contract: &mut Contract, amount: u128, now: u64) {
+        context(accounts(1), amount, now);
+        contract.deposit();
+    }
+
+    fn withdraw(contract: &mut Contract, amount: u128, now: u64) {
+        context(accounts(1), 0, now);
+        contract.withdraw_position(amount);
+    }
+
+    fn locked(amount: u128) -> u128 {
+        amount - amount * SYSTEM_FEE_BPS / BPS_DENOMINATOR
+    }
+
+    #[test]
+    fn redeposit_after_full_exit_starts_fresh_lock() {
+        let mut contract = setup_with_deposit();
+
+        let first_window = START_NS + TEST_LOCK_NS + MINUTE_NS;
+        withdraw(&mut contract, locked(10 * YOCTO_PER_NEAR), first_window);
+
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert!(!participant.active);
+        assert!(participant.lots.is_empty());
+
+        let redeposit_at = first_window + 30 * MINUTE_NS;
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, redeposit_at);
+
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert!(participant.active);
+        assert_eq!(participant.lock_started_at, redeposit_at);
+        assert_eq!(participant.lots.len(), 1);
+        assert_eq!(participant.lots[0].lock_started_at, redeposit_at);
+        assert_eq!(participant.lots[0].lot_id, 2);
+
+        context(accounts(1), 0, redeposit_at + MINUTE_NS);
+        assert!(!contract.is_exit_window_open(accounts(1)));
+
+        withdraw(
+            &mut contract,
+            locked(10 * YOCTO_PER_NEAR),
+            redeposit_at + TEST_LOCK_NS + MINUTE_NS,
+        );
+        assert_eq!(contract.get_position_balance(accounts(1)), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Exit window is not open")]
+    fn redeposit_cannot_exit_in_original_window_cycle() {
+        let mut contract = setup_with_deposit();
+
+        let first_window = START_NS + TEST_LOCK_NS + MINUTE_NS;
+        withdraw(&mut contract, locked(10 * YOCTO_PER_NEAR), first_window);
+
+        // One cycle later the original lock is in its second window while the
+        // redeposited lot is still inside its own fresh lock.
+        let cycle = TEST_LOCK_NS + TEST_EXIT_WINDOW_NS;
+        let redeposit_at = first_window + 30 * MINUTE_NS;
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, redeposit_at);
+
+        withdraw(&mut contract, locked(10 * YOCTO_PER_NEAR), first_window + cycle);
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount exceeds unlocked lots")]
+    fn top_up_lot_stays_locked_while_older_lot_is_open() {
+        let mut contract = setup_with_deposit();
+
+        let top_up_at = START_NS + TEST_LOCK_NS - MINUTE_NS;
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, top_up_at);
+
+        let window = START_NS + TEST_LOCK_NS + MINUTE_NS;
+        context(accounts(1), 0, window);
+        assert_eq!(
+            contract.get_unlocked_amount(accounts(1)),
+            locked(10 * YOCTO_PER_NEAR)
+        );
+
+        withdraw(&mut contract, locked(20 * YOCTO_PER_NEAR), window);
+    }
+
+    #[test]
+    fn exit_schedule_lists_upcoming_windows_per_lot() {
+        let mut contract = setup_with_deposit();
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS + 30 * MINUTE_NS);
+
+        let cycle = TEST_LOCK_NS + TEST_EXIT_WINDOW_NS;
+        let now = START_NS + MINUTE_NS;
+        context(accounts(1), 0, now);
+
+        let schedule = contract.get_exit_schedule(accounts(1), 3);
+        assert!(!schedule.exit_window_open);
+        assert_eq!(schedule.windows.len(), 3);
+        assert_eq!(schedule.windows[0].start, START_NS + TEST_LOCK_NS);
+        assert_eq!(schedule.windows[0].end, START_NS + TEST_LOCK_NS + TEST_EXIT_WINDOW_NS);
+        assert_eq!(schedule.windows[0].lot_ids, vec![1]);
+        assert_eq!(schedule.windows[0].amount.0, locked(10 * YOCTO_PER_NEAR));
+        assert_eq!(schedule.windows[1].start, START_NS + 30 * MINUTE_NS + TEST_LOCK_NS);
+        assert_eq!(schedule.windows[1].lot_ids, vec![2]);
+        assert_eq!(schedule.windows[2].start, START_NS + TEST_LOCK_NS + cycle);
+        assert_eq!(
+            schedule.ns_until_next_opening,
+            Some(START_NS + TEST_LOCK_NS - now)
+        );
+
+        let inside = START_NS + TEST_LOCK_NS + MINUTE_NS;
+        context(accounts(1), 0, inside);
+
+        let schedule = contract.get_exit_schedule(accounts(1), 1);
+        assert!(schedule.exit_window_open);
+        assert_eq!(schedule.windows[0].start, START_NS + TEST_LOCK_NS);
+        assert_eq!(
+            schedule.ns_until_current_window_closes,
+            Some(TEST_EXIT_WINDOW_NS - MINUTE_NS)
+        );
+    }
+
+    #[test]
+    fn early_exit_routes_decaying_penalty_to_pressure() {
+        let mut contract = setup_with_deposit();
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS + TEST_LOCK_NS / 2);
+
+        let now = START_NS + TEST_LOCK_NS / 2;
+        context(accounts(1), 0, now);
+
+        let amount = 12 * YOCTO_PER_NEAR;
+        let quote = contract.quote_early_exit(accounts(1), amount);
+
+        // Lot 1 is halfway to its window, lot 2 was just deposited.
+        assert_eq!(quote.lots.len(), 2);
+        assert_eq!(quote.lots[0].lot_id, 1);
+        assert_eq!(quote.lots[0].penalty_bps, EARLY_EXIT_MAX_PENALTY_BPS / 2);
+        assert_eq!(quote.lots[1].lot_id, 2);
+        assert_eq!(quote.lots[1].penalty_bps, EARLY_EXIT_MAX_PENALTY_BPS);
+
+        let pressure_before = contract.get_volcano_pressure();
+        contract.early_exit(amount);
+
+        let volcano = amount * VOLCANO_BPS / BPS_DENOMINATOR;
+        assert_eq!(
+            contract.get_volcano_pressure(),
+            pressure_before + volcano + quote.penalty.0
+        );
+
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert_eq!(participant.lots.len(), 1);
+        assert_eq!(participant.lots[0].lot_id, 2);
+        assert_eq!(participant.lots[0].lock_started_at, START_NS + TEST_LOCK_NS / 2);
+        assert_eq!(participant.position_balance, locked(20 * YOCTO_PER_NEAR) - amount);
+    }
+
+    #[test]
+    fn scheduled_withdrawal_waits_for_window_then_pays_receiver() {
+        let mut contract = setup_with_deposit();
+
+        let amount = locked(10 * YOCTO_PER_NEAR) / 2;
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + MINUTE_NS);
+        let request_id = contract.schedule_withdrawal(amount, accounts(2));
+
+        context(accounts(3), 0, START_NS + TEST_LOCK_NS / 2);
+        assert_eq!(contract.execute_scheduled_withdrawals(10), 0);
+        assert_eq!(contract.get_scheduled_withdrawal_count(), 1);
+
+        context(accounts(3), 0, START_NS + TEST_LOCK_NS + MINUTE_NS);
+        assert_eq!(contract.execute_scheduled_withdrawals(10), 1);
+        assert_eq!(contract.get_scheduled_withdrawal_count(), 0);
+
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert_eq!(participant.position_balance, locked(10 * YOCTO_PER_NEAR) - amount);
+
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + TEST_LOCK_NS + 2 * MINUTE_NS);
+        let second = contract.schedule_withdrawal(amount, accounts(2));
+        assert!(second > request_id);
+        contract.cancel_scheduled_withdrawal(second);
+        assert!(contract.get_scheduled_withdrawals(accounts(1)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Scheduled withdrawals exceed position balance")]
+    fn scheduled_withdrawals_cannot_exceed_position() {
+        let mut contract = setup_with_deposit();
+
+        let amount = locked(10 * YOCTO_PER_NEAR) / 2;
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + MINUTE_NS);
+        contract.schedule_withdrawal(amount, accounts(1));
+        contract.schedule_withdrawal(amount + 1, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Scheduled amount below minimum")]
+    fn scheduled_withdrawal_below_minimum_is_rejected() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + MINUTE_NS);
+        contract.schedule_withdrawal(MIN_SCHEDULED_WITHDRAWAL_YOCTO - 1, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many scheduled withdrawals")]
+    fn scheduled_withdrawals_are_capped_per_account() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + MINUTE_NS);
+        for _ in 0..=MAX_SCHEDULED_WITHDRAWALS_PER_ACCOUNT {
+            contract.schedule_withdrawal(MIN_SCHEDULED_WITHDRAWAL_YOCTO, accounts(1));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach deposit to cover storage")]
+    fn scheduled_withdrawal_requires_storage_deposit() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), 0, START_NS + MINUTE_NS);
+        contract.schedule_withdrawal(MIN_SCHEDULED_WITHDRAWAL_YOCTO, accounts(1));
+    }
+
+    #[test]
+    fn direct_withdrawal_trims_scheduled_withdrawals() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + MINUTE_NS);
+        let request_id = contract.schedule_withdrawal(4 * YOCTO_PER_NEAR, accounts(1));
+
+        // Leaves 3 NEAR of position against 4 NEAR scheduled.
+        let remaining = 3 * YOCTO_PER_NEAR;
+        withdraw(
+            &mut contract,
+            locked(10 * YOCTO_PER_NEAR) - remaining,
+            START_NS + TEST_LOCK_NS + MINUTE_NS,
+        );
+
+        let requests = contract.get_scheduled_withdrawals(accounts(1));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].request_id, request_id);
+        assert_eq!(requests[0].amount, remaining);
+        assert!(requests[0].storage_deposit > 0);
+    }
+
+    #[test]
+    fn crank_sweeps_expired_claims_and_pays_capped_bounty() {
+        let mut contract = setup();
+
+        context(accounts(2), 10 * YOCTO_PER_NEAR, START_NS);
+        contract.fund_keeper_budget();
+
+        // Enough pressure for phase 1 with a single eligible wallet.
+        deposit(&mut contract, 100 * YOCTO_PER_NEAR, START_NS);
+        let threshold = PHASE_MILESTONES_YOCTO[0];
+        contract.volcano_pressure = threshold;
+        context(accounts(1), 0, AGED_NS);
+        contract.try_trigger_eruption();
+        assert_eq!(contract.get_eruption_count(), 1);
+
+        let deadline = contract.eruptions[0].claim_deadline;
+        context(accounts(3), 0, deadline + 1);
+        assert_eq!(contract.get_crank_work().sweepable_eruptions, 1);
+
+        let pressure_before = contract.get_volcano_pressure();
+        let result = contract.crank();
+
+        assert_eq!(result.sweeps, 1);
+        assert_eq!(result.bounty.0, CRANK_BOUNTY_PER_STEP_YOCTO);
+        assert_eq!(
+            contract.get_volcano_pressure(),
+            pressure_before + contract.eruptions[0].share_per_wallet
+        );
+        assert!(contract.eruptions[0].swept);
+
+        let result = contract.crank();
+        assert_eq!(result.sweeps, 0);
+        assert_eq!(result.bounty.0, 0);
+        assert_eq!(
+            contract.get_keeper_budget(),
+            10 * YOCTO_PER_NEAR - CRANK_BOUNTY_PER_STEP_YOCTO
+        );
+    }
+
+    #[test]
+    fn scheduled_withdrawal_scan_resumes_past_requests_not_yet_due() {
+        let mut contract = setup();
+        contract.add_to_allowlist(accounts(2), Some(100 * YOCTO_PER_NEAR));
+
+        // accounts(2) locks later, so its request sits first but is not due.
+        context(accounts(2), 10 * YOCTO_PER_NEAR, START_NS + TEST_LOCK_NS / 2);
+        contract.deposit();
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS + TEST_LOCK_NS / 2);
+        contract.participants[1].lots[0].lock_started_at = START_NS;
+        contract.participants[1].lock_started_at = START_NS;
+
+        context(accounts(2), SCHEDULE_STORAGE_YOCTO, START_NS + TEST_LOCK_NS / 2);
+        let blocked = contract.schedule_withdrawal(YOCTO_PER_NEAR, accounts(2));
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + TEST_LOCK_NS / 2);
+        contract.schedule_withdrawal(YOCTO_PER_NEAR, accounts(1));
+
+        context(accounts(3), 0, START_NS + TEST_LOCK_NS + MINUTE_NS);
+        assert_eq!(contract.execute_scheduled_withdrawals(1), 0);
+        assert_eq!(contract.execute_scheduled_withdrawals(1), 1);
+
+        let requests = contract.get_scheduled_withdrawals(accounts(2));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].request_id, blocked);
+        assert!(contract.get_scheduled_withdrawals(accounts(1)).is_empty());
+    }
+
+    #[test]
+    fn crank_pays_no_bounty_for_dropped_withdrawals() {
+        let mut contract = setup();
+
+        context(accounts(2), 10 * YOCTO_PER_NEAR, START_NS);
+        contract.fund_keeper_budget();
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + MINUTE_NS);
+        contract.schedule_withdrawal(4 * YOCTO_PER_NEAR, accounts(1));
+        contract.participants[0].position_balance = YOCTO_PER_NEAR;
+
+        context(accounts(3), 0, START_NS + TEST_LOCK_NS + MINUTE_NS);
+        let result = contract.crank();
+        assert_eq!(result.withdrawals, 0);
+        assert_eq!(result.bounty.0, 0);
+        assert_eq!(contract.get_scheduled_withdrawal_count(), 0);
+        assert_eq!(contract.get_keeper_budget(), 10 * YOCTO_PER_NEAR);
+    }
+
+    #[test]
+    fn large_eruption_scan_resumes_across_calls() {
+        let mut contract = setup();
+        contract.launch_stage = LaunchStage::Public;
+        contract.tvl_cap = None;
+
+        let count = 2 * ERUPTION_INLINE_WORK_BUDGET + 10;
+        for i in 0..count {
+            let account: AccountId = format!("user{}.near", i).parse().unwrap();
+            context(account, 2 * YOCTO_PER_NEAR, START_NS);
+            contract.deposit();
+        }
+
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[0];
+        context(accounts(1), 0, AGED_NS);
+        contract.try_trigger_eruption();
+
+        let pending = contract.get_pending_eruption().unwrap();
+        assert_eq!(pending.cursor, ERUPTION_INLINE_WORK_BUDGET);
+        assert_eq!(pending.participant_count, count);
+        assert_eq!(contract.get_eruption_count(), 0);
+
+        // Deposits keep working while the scan is outstanding, and a newcomer
+        // does not join the eruption already in progress.
+        context(accounts(2), 2 * YOCTO_PER_NEAR, AGED_NS + MINUTE_NS);
+        contract.deposit();
+        assert_eq!(
+            contract.get_pending_eruption().unwrap().cursor,
+            2 * ERUPTION_INLINE_WORK_BUDGET
+        );
+
+        context(accounts(3), 0, AGED_NS + 2 * MINUTE_NS);
+        let result = contract.crank();
+
+        assert_eq!(result.eruptions, 1);
+        assert!(contract.get_pending_eruption().is_none());
+        assert_eq!(contract.eruptions[0].eligible_count, count);
+        assert!(!contract.eruptions[0].eligible_accounts.contains(&accounts(2)));
+    }
+
+    #[test]
+    fn position_weighted_mode_pays_pro_rata() {
+        let mut contract = setup();
+        contract.distribution_mode = DistributionMode::PositionWeighted;
+        contract.add_to_allowlist(accounts(2), Some(100 * YOCTO_PER_NEAR));
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+        context(accounts(2), 30 * YOCTO_PER_NEAR, START_NS);
+        contract.deposit();
+
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[0];
+        context(accounts(3), 0, AGED_NS);
+        contract.crank();
+
+        let eruption = &contract.eruptions[0];
+        let pool = PHASE_MILESTONES_YOCTO[0] * 75 / 100;
+        let small = eruption.share_of(&accounts(1));
+        let large = eruption.share_of(&accounts(2));
+
+        assert_eq!(small, pool / 4);
+        assert_eq!(large, pool * 3 / 4);
+        assert_eq!(eruption.share_per_wallet, 0);
+        assert_eq!(
+            eruption.distribution_pool + eruption.unallocated_remainder,
+            pool
+        );
+
+        context(accounts(2), 0, AGED_NS + MINUTE_NS);
+        contract.claim_eruption(1);
+        assert_eq!(contract.eruptions[0].claimed_amount, large);
+    }
+
+    #[test]
+    fn just_in_time_deposit_is_not_eligible() {
+        let mut contract = setup();
+        contract.add_to_allowlist(accounts(2), Some(100 * YOCTO_PER_NEAR));
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+
+        // accounts(2) deposits the amount that crosses the milestone.
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[0] - 1;
+        context(accounts(2), 10 * YOCTO_PER_NEAR, AGED_NS + MINUTE_NS);
+        contract.deposit();
+
+        assert_eq!(contract.get_eruption_count(), 1);
+
+        let eruption = &contract.eruptions[0];
+        assert_eq!(eruption.eligible_accounts, vec![accounts(1)]);
+        assert_eq!(eruption.share_of(&accounts(2)), 0);
+        assert_eq!(contract.get_eligible_position(accounts(2)), 0);
+
+        context(accounts(2), 0, AGED_NS + TEST_MIN_HOLDING_AGE_NS + MINUTE_NS);
+        assert_eq!(
+            contract.get_eligible_position(accounts(2)),
+            locked(10 * YOCTO_PER_NEAR)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Wallet not eligible for this eruption")]
+    fn just_in_time_deposit_cannot_claim() {
+        let mut contract = setup();
+        contract.add_to_allowlist(accounts(2), Some(100 * YOCTO_PER_NEAR));
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+
+        context(accounts(2), 10 * YOCTO_PER_NEAR, AGED_NS);
+        contract.deposit();
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[0];
+
+        context(accounts(3), 0, AGED_NS + MINUTE_NS);
+        contract.crank();
+
+        context(accounts(2), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.claim_eruption(1);
+    }
+
+    fn attester(seed: u8) -> (SigningKey, PublicKey) {
+        let signing_key = SigningKey::from_bytes(&[seed; 32]);
+        let public_key = PublicKey::from_parts(
+            CurveType::ED25519,
+            signing_key.verifying_key().to_bytes().to_vec(),
+        )
+        .unwrap();
+        (signing_key, public_key)
+    }
+
+    #[test]
+    fn attestation_policy_gates_eligibility() {
+        let mut contract = setup();
+        contract.add_to_allowlist(accounts(2), Some(100 * YOCTO_PER_NEAR));
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+        context(accounts(2), 10 * YOCTO_PER_NEAR, START_NS);
+        contract.deposit();
+
+        let (signing_key, public_key) = attester(7);
+        context(accounts(0), 0, START_NS);
+        contract.set_eligibility_policy(0, Some(public_key), true);
+
+        let expires_at = AGED_NS + TEST_LOCK_NS;
+        let message = contract.get_attestation_message(accounts(1), expires_at);
+        let signature = signing_key.sign(message.as_bytes()).to_bytes().to_vec();
+
+        // Relayed by a third party on behalf of accounts(1).
+        context(accounts(3), 0, START_NS + MINUTE_NS);
+        contract.submit_eligibility_attestation(accounts(1), expires_at, signature.into());
+
+        context(accounts(3), 0, AGED_NS);
+        assert!(contract.get_eligible_position(accounts(1)) > 0);
+        assert_eq!(contract.get_eligible_position(accounts(2)), 0);
+
+        // Rotating the attester key invalidates earlier attestations.
+        let (_, other_key) = attester(9);
+        context(accounts(0), 0, AGED_NS);
+        contract.set_eligibility_policy(0, Some(other_key), true);
+        assert_eq!(contract.get_eligible_position(accounts(1)), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid attestation signature")]
+    fn attestation_for_another_account_is_rejected() {
+        let mut contract = setup();
+
+        let (signing_key, public_key) = attester(7);
+        contract.set_eligibility_policy(0, Some(public_key), true);
+
+        let expires_at = AGED_NS;
+        let message = contract.get_attestation_message(accounts(2), expires_at);
+        let signature = signing_key.sign(message.as_bytes()).to_bytes().to_vec();
+
+        context(accounts(1), 0, START_NS);
+        contract.submit_eligibility_attestation(accounts(1), expires_at, signature.into());
+    }
+
+    #[test]
+    fn min_account_age_counts_from_first_deposit() {
+        let mut contract = setup();
+        contract.set_eligibility_policy(TEST_LOCK_NS, None, false);
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+
+        context(accounts(1), 0, AGED_NS);
+        assert_eq!(contract.get_eligible_position(accounts(1)), 0);
+
+        context(accounts(1), 0, START_NS + TEST_LOCK_NS);
+        assert_eq!(
+            contract.get_eligible_position(accounts(1)),
+            locked(10 * YOCTO_PER_NEAR)
+        );
+    }
+
+    fn erupt_twice(contract: &mut Contract) {
+        deposit(contract, 10 * YOCTO_PER_NEAR, START_NS);
+
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[0];
+        context(accounts(3), 0, AGED_NS);
+        contract.crank();
+
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[1];
+        context(accounts(3), 0, AGED_NS + MINUTE_NS);
+        contract.crank();
+
+        assert_eq!(contract.get_eruption_count(), 2);
+    }
+
+    #[test]
+    fn claim_all_marks_every_open_eruption() {
+        let mut contract = setup();
+        erupt_twice(&mut contract);
+
+        context(accounts(1), 0, AGED_NS + 2 * MINUTE_NS);
+        let claimable = contract.get_claimable(accounts(1));
+        assert_eq!(claimable.eruptions.len(), 2);
+        assert_eq!(
+            claimable.total.0,
+            contract.eruptions[0].share_per_wallet + contract.eruptions[1].share_per_wallet
+        );
+
+        let _ = contract.claim_all(10);
+
+        assert!(contract.get_claimable(accounts(1)).eruptions.is_empty());
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert_eq!(participant.claimed_eruption_ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn failed_claim_all_transfer_reopens_claims() {
+        let mut contract = setup();
+        erupt_twice(&mut contract);
+
+        context(accounts(1), 0, AGED_NS + 2 * MINUTE_NS);
+        let before = contract.get_claimable(accounts(1));
+        let _ = contract.claim_all(10);
+
+        let claims: Vec<ClaimedShare> = before
+            .eruptions
+            .iter()
+            .map(|e| ClaimedShare {
+                eruption_id: e.eruption_id,
+                share: e.share,
+            })
+            .collect();
+        contract.rollback_claims(&accounts(1), &claims);
+
+        let after = contract.get_claimable(accounts(1));
+        assert_eq!(after.total.0, before.total.0);
+        assert_eq!(contract.eruptions[0].claimed_count, 0);
+        assert_eq!(contract.eruptions[1].claimed_amount, 0);
+        assert!(contract
+            .get_participant(accounts(1))
+            .unwrap()
+            .claimed_eruption_ids
+            .is_empty());
+    }
+
+    fn lift_deposit_caps(contract: &mut Contract) {
+        context(accounts(0), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.add_to_allowlist(accounts(1), Some(1_000_000 * YOCTO_PER_NEAR));
+        contract.set_tvl_cap(None);
+    }
+
+    #[test]
+    fn compounded_claim_locks_share_as_new_lot_after_fee() {
+        let mut contract = setup();
+        erupt_twice(&mut contract);
+
+        lift_deposit_caps(&mut contract);
+
+        let share = contract.eruptions[0].share_per_wallet;
+        let before = contract.get_position_balance(accounts(1));
+        let now = AGED_NS + 2 * MINUTE_NS;
+
+        context(accounts(1), 0, now);
+        contract.claim_eruption_and_compound(1);
+
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert_eq!(participant.position_balance, before + locked(share));
+        assert_eq!(participant.claimed_eruption_ids, vec![1]);
+
+        let lot = participant.lots.last().unwrap();
+        assert_eq!(lot.amount, locked(share));
+        assert_eq!(lot.lock_started_at, now);
+        assert_eq!(contract.eruptions[0].claimed_amount, share);
+    }
+
+    #[test]
+    fn auto_compound_claim_all_credits_one_lot() {
+        let mut contract = setup();
+        erupt_twice(&mut contract);
+
+        lift_deposit_caps(&mut contract);
+
+        let total = contract.get_claimable(accounts(1)).total.0;
+        let before = contract.get_participant(accounts(1)).unwrap();
+
+        context(accounts(1), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.set_auto_compound(true);
+        assert!(contract.get_auto_compound(accounts(1)));
+
+        let _ = contract.claim_all(10);
+
+        let after = contract.get_participant(accounts(1)).unwrap();
+        assert_eq!(after.lots.len(), before.lots.len() + 1);
+        assert_eq!(after.position_balance, before.position_balance + locked(total));
+        assert_eq!(after.claimed_eruption_ids, vec![1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposit exceeds per-account max position")]
+    fn compounded_claim_respects_deposit_caps() {
+        let mut contract = setup();
+        erupt_twice(&mut contract);
+
+        context(accounts(1), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.claim_eruption_and_compound(1);
+    }
+
+    fn last_claim_event() -> String {
+        get_logs()
+            .into_iter()
+            .rev()
+            .find(|log| log.contains("\"event\":\"eruption_claimed\""))
+            .expect("No eruption_claimed event")
+    }
+
+    #[test]
+    fn claim_to_and_agent_claims_route_payouts() {
+        let mut contract = setup();
+        erupt_twice(&mut contract);
+
+        context(accounts(1), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.claim_eruption_to(1, accounts(4));
+        let event = last_claim_event();
+        assert!(event.contains(&format!("\"receiver_id\":\"{}\"", accounts(4))));
+
+        contract.add_claim_agent(accounts(2));
+        assert_eq!(contract.get_claim_agents(accounts(1)), vec![accounts(2)]);
+
+        context(accounts(2), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.claim_eruption_on_behalf(accounts(1), 2);
+        let event = last_claim_event();
+        assert!(event.contains(&format!("\"claimed_by\":\"{}\"", accounts(2))));
+        assert!(event.contains(&format!("\"receiver_id\":\"{}\"", accounts(1))));
+
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert_eq!(participant.claimed_eruption_ids, vec![1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not a claim agent for this participant")]
+    fn removed_claim_agent_cannot_claim() {
+        let mut contract = setup();
+        erupt_twice(&mut contract);
+
+        context(accounts(1), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.add_claim_agent(accounts(2));
+        contract.remove_claim_agent(accounts(2));
+
+        context(accounts(2), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.claim_eruption_on_behalf(accounts(1), 1);
+    }
+
+    #[test]
+    fn beneficiary_inherits_after_inactivity_and_challenge() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), 0, START_NS);
+        contract.set_beneficiary(Some(accounts(2)), TEST_MIN_INACTIVITY_NS);
+
+        let window_start = START_NS + TEST_LOCK_NS;
+        context(accounts(2), 0, window_start + MINUTE_NS);
+        contract.start_inheritance_claim(accounts(1));
+
+        let status = contract.get_inheritance_status(accounts(1)).unwrap();
+        let claim = status.inheritance_claim.unwrap();
+        assert_eq!(
+            claim.executable_at,
+            window_start + MINUTE_NS + TEST_INHERITANCE_CHALLENGE_NS
+        );
+
+        context(accounts(2), 0, claim.executable_at);
+        contract.inherit_position(accounts(1), locked(10 * YOCTO_PER_NEAR));
+
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert_eq!(participant.position_balance, 0);
+        assert!(!participant.active);
+    }
+
+    #[test]
+    #[should_panic(expected = "No inheritance claim")]
+    fn participant_call_cancels_inheritance_claim() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), 0, START_NS);
+        contract.set_beneficiary(Some(accounts(2)), TEST_MIN_INACTIVITY_NS);
+
+        let window_start = START_NS + TEST_LOCK_NS;
+        context(accounts(2), 0, window_start);
+        contract.start_inheritance_claim(accounts(1));
+
+        context(accounts(1), 0, window_start + MINUTE_NS);
+        contract.set_auto_compound(false);
+        assert!(contract
+            .get_inheritance_status(accounts(1))
+            .unwrap()
+            .inheritance_claim
+            .is_none());
+
+        context(accounts(2), 0, window_start + 2 * TEST_INHERITANCE_CHALLENGE_NS);
+        contract.inherit_position(accounts(1), locked(10 * YOCTO_PER_NEAR));
+    }
+
+    #[test]
+    #[should_panic(expected = "Participant is not inactive")]
+    fn beneficiary_cannot_start_claim_while_participant_active() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), 0, START_NS);
+        contract.set_beneficiary(Some(accounts(2)), TEST_MIN_INACTIVITY_NS);
+
+        context(accounts(2), 0, START_NS + TEST_MIN_INACTIVITY_NS - 1);
+        contract.start_inheritance_claim(accounts(1));
+    }
+
+    #[test]
+    fn phase_milestones_append_after_timelock() {
+        let mut contract = setup();
+        let next = 2_000_000_000_000 * YOCTO_PER_NEAR;
+
+        contract.propose_phase_milestones(vec![next]);
+        assert_eq!(contract.get_phase_threshold(17), None);
+
+        context(accounts(3), 0, START_NS + TEST_GOVERNANCE_TIMELOCK_NS);
+        contract.execute_phase_milestone_proposal();
+
+        assert_eq!(contract.get_phase_milestones().len(), 17);
+        assert_eq!(contract.get_phase_threshold(17), Some(next));
+        assert!(contract.get_phase_rule().pending_proposal.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Phase milestone timelock not elapsed")]
+    fn phase_milestones_cannot_skip_timelock() {
+        let mut contract = setup();
+        contract.propose_phase_milestones(vec![2_000_000_000_000 * YOCTO_PER_NEAR]);
+
+        context(accounts(3), 0, START_NS + TEST_GOVERNANCE_TIMELOCK_NS - 1);
+        contract.execute_phase_milestone_proposal();
+    }
+
+    #[test]
+    fn geometric_continuation_extends_past_table() {
+        let contract = setup_with_phase_table(Some(PhaseTableConfig {
+            milestones: vec![100 * YOCTO_PER_NEAR, 200 * YOCTO_PER_NEAR],
+            continuation: Some(PhaseContinuation {
+                ratio_bps: 15_000,
+                max_phases: Some(2),
+            }),
+        }));
+
+        assert_eq!(contract.get_phase_threshold(2), Some(200 * YOCTO_PER_NEAR));
+        assert_eq!(contract.get_phase_threshold(3), Some(300 * YOCTO_PER_NEAR));
+        assert_eq!(contract.get_phase_threshold(4), Some(450 * YOCTO_PER_NEAR));
+        assert_eq!(contract.get_phase_threshold(5), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Phase milestones can only be appended for phases not yet started")]
+    fn phase_milestones_cannot_rewrite_reached_continuation_phases() {
+        let mut contract = setup_with_phase_table(Some(PhaseTableConfig {
+            milestones: vec![PHASE_MILESTONES_YOCTO[0]],
+            continuation: Some(PhaseContinuation {
+                ratio_bps: 25_000,
+                max_phases: None,
+            }),
+        }));
+        erupt_twice(&mut contract);
+
+        context(accounts(0), 0, AGED_NS + 2 * MINUTE_NS);
+        contract.propose_phase_milestones(vec![PHASE_MILESTONES_YOCTO[2]]);
+    }
+
+    #[test]
+    fn claim_window_is_independent_and_recorded_per_snapshot() {
+        let mut contract = setup_with_deposit();
+
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[0];
+        context(accounts(3), 0, AGED_NS);
+        contract.crank();
+
+        context(accounts(0), 0, AGED_NS);
+        contract.set_claim_window_ns(3 * TEST_CLAIM_WINDOW_NS);
+
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[1];
+        context(accounts(3), 0, AGED_NS + MINUTE_NS);
+        contract.crank();
+
+        let first = &contract.eruptions[0];
+        assert_eq!(first.claim_window_ns, TEST_CLAIM_WINDOW_NS);
+        assert_eq!(first.claim_deadline, AGED_NS + TEST_CLAIM_WINDOW_NS);
+        assert_ne!(first.claim_window_ns, contract.exit_window_ns);
+
+        let second = &contract.eruptions[1];
+        assert_eq!(second.claim_window_ns, 3 * TEST_CLAIM_WINDOW_NS);
+        assert_eq!(
+            second.claim_deadline,
+            AGED_NS + MINUTE_NS + 3 * TEST_CLAIM_WINDOW_NS
+        );
+    }
+
+    #[test]
+    fn simulation_matches_the_snapshot_it_projects() {
+        let mut contract = setup_with_deposit();
+
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[0] - YOCTO_PER_NEAR;
+        context(accounts(3), 0, AGED_NS);
+
+        let projection = contract.simulate_next_eruption();
+        assert_eq!(projection.phase_number, 1);
+        assert_eq!(projection.shortfall.0, YOCTO_PER_NEAR);
+        assert_eq!(projection.distribution_pool.0, PHASE_MILESTONES_YOCTO[0] * 75 / 100);
+        assert_eq!(projection.eligible_count, 1);
+
+        let quote = contract.quote_deposit_impact(Some(accounts(1)), 10 * YOCTO_PER_NEAR);
+        assert!(!quote.triggers_eruption);
+        assert_eq!(quote.pressure_added.0, 10 * YOCTO_PER_NEAR * VOLCANO_BPS / BPS_DENOMINATOR);
+
+        let quote = contract.quote_deposit_impact(Some(accounts(1)), 100 * YOCTO_PER_NEAR);
+        assert!(quote.triggers_eruption);
+        assert_eq!(quote.eruptions_triggered, 1);
+
+        contract.volcano_pressure = PHASE_MILESTONES_YOCTO[0];
+        contract.crank();
+
+        let snapshot = &contract.eruptions[0];
+        assert_eq!(snapshot.share_per_wallet, projection.share_per_wallet.0);
+        assert_eq!(snapshot.eligible_count, projection.eligible_count);
+        assert_eq!(contract.simulate_next_eruption().phase_number, 2);
+    }
+
+    #[test]
+    fn deposit_impact_chains_milestones_with_carried_pressure() {
+        let contract = setup_with_deposit();
+        context(accounts(3), 0, AGED_NS);
+
+        // Everything but the phase 1 pool carries forward, so enough pressure
+        // for both milestones plus that pool crosses two in one deposit.
+        let needed = PHASE_MILESTONES_YOCTO[1] + PHASE_MILESTONES_YOCTO[0] * 75 / 100;
+        let amount = needed * BPS_DENOMINATOR / VOLCANO_BPS + BPS_DENOMINATOR;
+
+        let quote = contract.quote_deposit_impact(Some(accounts(1)), amount);
+        assert_eq!(quote.eruptions_triggered, 2);
+        assert_eq!(
+            quote.pressure_after_eruptions.0,
+            quote.pressure_after.0
+                - PHASE_MILESTONES_YOCTO[0] * 75 / 100
+                - PHASE_MILESTONES_YOCTO[1] * 60 / 100
+        );
+    }
+
+    #[test]
+    fn deposit_impact_reports_whether_the_deposit_would_be_accepted() {
+        let mut contract = setup_with_deposit();
+
+        let quote = contract.quote_deposit_impact(Some(accounts(1)), 10 * YOCTO_PER_NEAR);
+        assert!(quote.deposit_allowed);
+        assert_eq!(quote.locked_position.0, locked(10 * YOCTO_PER_NEAR));
+        assert_eq!(
+            quote.max_locked_position_addable,
+            Some(U128(100 * YOCTO_PER_NEAR - locked(10 * YOCTO_PER_NEAR)))
+        );
+
+        let quote = contract.quote_deposit_impact(Some(accounts(1)), 200 * YOCTO_PER_NEAR);
+        assert!(!quote.deposit_allowed);
+        assert_eq!(
+            quote.rejection_reason.as_deref(),
+            Some("Deposit exceeds per-account max position")
         );
 
-        require!(
-            !self.participants[participant_index]
-                .claimed_eruption_ids
-                .contains(&eruption_id),
-            "Already claimed"
+        let quote = contract.quote_deposit_impact(Some(accounts(2)), YOCTO_PER_NEAR);
+        assert_eq!(
+            quote.rejection_reason.as_deref(),
+            Some("Account not allowlisted before public launch")
         );
 
-        let share = self.eruptions[eruption_index].share_per_wallet;
-        require!(share > 0, "No claimable share");
+        // Quoting an absurd amount reports a rejection instead of overflowing.
+        let quote = contract.quote_deposit_impact(Some(accounts(1)), u128::MAX);
+        assert!(!quote.deposit_allowed);
+        assert_eq!(quote.pressure_added.0, mul_div(u128::MAX, VOLCANO_BPS, BPS_DENOMINATOR));
 
-        self.participants[participant_index]
-            .claimed_eruption_ids
-            .push(eruption_id);
+        // Anonymous quotes need an account until public launch.
+        let quote = contract.quote_deposit_impact(None, YOCTO_PER_NEAR);
+        assert_eq!(
+            quote.rejection_reason.as_deref(),
+            Some("Allowlisted account required before public launch")
+        );
 
-        self.eruptions[eruption_index].claimed_count += 1;
+        let pressure = contract.volcano_pressure;
+        contract.volcano_pressure = u128::MAX - 1;
+        let quote = contract.quote_deposit_impact(Some(accounts(1)), 10 * YOCTO_PER_NEAR);
+        assert_eq!(
+            quote.rejection_reason.as_deref(),
+            Some("Deposit would overflow volcano pressure")
+        );
+        assert_eq!(quote.pressure_after.0, u128::MAX);
+        contract.volcano_pressure = pressure;
 
-        Promise::new(caller.clone())
-            .transfer(NearToken::from_yoctonear(share));
+        context(accounts(0), 0, START_NS);
+        contract.set_pause_flags(true, false, false);
+        let quote = contract.quote_deposit_impact(Some(accounts(1)), YOCTO_PER_NEAR);
+        assert_eq!(quote.rejection_reason.as_deref(), Some("Deposits paused"));
+    }
 
-        env::log_str(&format!(
-            "ERUPTION_CLAIMED caller={} eruption_id={} share={} claimed_count={} eligible_count={}",
-            caller,
-            eruption_id,
-            share,
-            self.eruptions[eruption_index].claimed_count,
-            self.eruptions[eruption_index].eligible_count
-        ));
+    #[test]
+    fn early_exit_trims_scheduled_withdrawals_newest_first() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), SCHEDULE_STORAGE_YOCTO, START_NS + MINUTE_NS);
+        let first = contract.schedule_withdrawal(4 * YOCTO_PER_NEAR, accounts(1));
+        let second = contract.schedule_withdrawal(3 * YOCTO_PER_NEAR, accounts(1));
+        let third = contract.schedule_withdrawal(2 * YOCTO_PER_NEAR, accounts(1));
+
+        // Leaves 5 NEAR of position against 9 NEAR scheduled.
+        let remaining = 5 * YOCTO_PER_NEAR;
+        contract.early_exit(locked(10 * YOCTO_PER_NEAR) - remaining);
+
+        let requests = contract.get_scheduled_withdrawals(accounts(1));
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].request_id, first);
+        assert_eq!(requests[0].amount, 4 * YOCTO_PER_NEAR);
+        assert_eq!(requests[1].request_id, second);
+        assert_eq!(requests[1].amount, YOCTO_PER_NEAR);
+        assert!(requests.iter().all(|r| r.request_id != third));
+
+        let logs = get_logs();
+        assert!(logs.iter().any(|log| log.contains("\"event\":\"withdrawal_trimmed\"")));
     }
 
-    fn try_trigger_eruption(&mut self) {
-        loop {
-            let phase_index = self.eruption_count as usize;
-
-            let threshold = match self.phase_threshold_by_index(phase_index) {
-                Some(value) => value,
-                None => {
-                    env::log_str("ERUPTION_CHECK_STOPPED reason=no_more_supported_phases");
-                    return;
-                }
-            };
+    fn freeze_tpi(contract: &mut Contract) {
+        deposit(contract, 10 * YOCTO_PER_NEAR, START_NS);
 
-            if self.volcano_pressure < threshold {
-                return;
-            }
+        context(accounts(0), 0, START_NS + MINUTE_NS);
+        let amount = contract.get_tpi_deployment_power();
+        let id = contract.tpi_record_deployment(amount, "venue".to_string(), String::new());
+        contract.tpi_record_return(id, amount / 2);
 
-            self.trigger_eruption_for_phase(phase_index, threshold);
-        }
+        assert_eq!(contract.get_tpi_state().status, TpiStatus::Frozen);
+        assert_eq!(contract.get_tpi_deployment_power(), 0);
     }
 
-    fn trigger_eruption_for_phase(&mut self, phase_index: usize, threshold: u128) {
-        require!(self.volcano_pressure >= threshold, "Threshold not reached");
+    #[test]
+    fn tpi_review_reset_lifts_a_freeze() {
+        let mut contract = setup();
+        freeze_tpi(&mut contract);
 
-        let phase_number = self.eruption_count + 1;
-        let now = env::block_timestamp();
+        contract.tpi_review_reset("loss written off after review".to_string());
 
-        let distribution_pool = if phase_number == 1 {
-            threshold * 75 / 100
-        } else {
-            threshold * 60 / 100
-        };
+        let state = contract.get_tpi_state();
+        assert_eq!(state.status, TpiStatus::Full);
+        assert_eq!(state.sample_count, 0);
+        assert!(contract.get_tpi_deployments()[0].excluded_from_trend);
 
-        let retained_amount = threshold
-            .checked_sub(distribution_pool)
-            .expect("Retained underflow");
+        let power = contract.get_tpi_deployment_power();
+        assert!(power > 0);
+        contract.tpi_record_deployment(power, "venue".to_string(), String::new());
+    }
 
-        let eligible_accounts = self.eligible_accounts();
-        let eligible_count = eligible_accounts.len() as u64;
+    #[test]
+    #[should_panic(expected = "Only owner")]
+    fn treasury_wallet_cannot_review_its_own_freeze() {
+        let mut contract = setup();
+        freeze_tpi(&mut contract);
 
-        let share_per_wallet = if eligible_count > 0 {
-            distribution_pool / eligible_count as u128
-        } else {
-            0
-        };
+        context("treasury.near".parse().unwrap(), 0, START_NS + MINUTE_NS);
+        contract.tpi_review_reset("self review".to_string());
+    }
 
-        let allocated_claim_pool = share_per_wallet * eligible_count as u128;
-        let unallocated_remainder = distribution_pool
-            .checked_sub(allocated_claim_pool)
-            .expect("Remainder underflow");
+    #[test]
+    fn deposit_event_precedes_the_eruption_it_triggers() {
+        let mut contract = setup_with_deposit();
 
-        let extra_pressure = self
-            .volcano_pressure
-            .checked_sub(threshold)
-            .expect("Extra pressure underflow");
+        let milestone = PHASE_MILESTONES_YOCTO[0];
+        contract.volcano_pressure = milestone - 1;
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, AGED_NS);
+        assert_eq!(contract.get_eruption_count(), 1);
 
-        self.volcano_pressure = retained_amount
-            .checked_add(unallocated_remainder)
-            .and_then(|v| v.checked_add(extra_pressure))
-            .expect("Pressure carry overflow");
+        let logs = get_logs();
+        let position = |event: &str| {
+            logs.iter()
+                .position(|log| log.contains(&format!("\"event\":\"{}\"", event)))
+                .expect("Event not logged")
+        };
+        assert!(position("deposit") < position("eruption_snapshot"));
 
-        let eruption_id = self.eruption_count + 1;
+        // The payload reports pressure as it stood before the eruption drained it.
+        let volcano = 10 * YOCTO_PER_NEAR * VOLCANO_BPS / BPS_DENOMINATOR;
+        let deposit_log = &logs[position("deposit")];
+        assert!(deposit_log.contains(&format!("\"pressure\":\"{}\"", milestone - 1 + volcano)));
+    }
 
-        let snapshot = EruptionSnapshot {
-            eruption_id,
-            phase_number,
-            threshold,
-            distribution_pool: allocated_claim_pool,
-            retained_amount,
-            unallocated_remainder,
-            share_per_wallet,
-            eligible_count,
-            claimed_count: 0,
-            created_at: now,
-            claim_deadline: now
-                .checked_add(self.exit_window_ns)
-                .expect("Claim deadline overflow"),
-            eligible_accounts,
-        };
+    #[test]
+    fn mul_div_handles_wide_products() {
+        let big = 1_000_000_000_000 * YOCTO_PER_NEAR;
+        assert_eq!(mul_div(big, big / 3, big), big / 3);
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), u128::MAX);
+        assert_eq!(mul_div(7, 5, 3), 11);
+        assert_eq!(mul_div(0, big, big), 0);
+    }
 
-        self.eruptions.push(snapshot);
-        self.eruption_count += 1;
+    #[test]
+    fn partial_exit_keeps_tenure_and_remaining_lot_schedule() {
+        let mut contract = setup_with_deposit();
 
-        env::log_str(&format!(
-            "ERUPTION_SNAPSHOT eruption_id={} phase={} threshold={} allocated_distribution={} retained={} unallocated_remainder={} share_per_wallet={} eligible_count={} carried_pressure={} next_threshold={}",
-            eruption_id,
-            phase_number,
-            threshold,
-            allocated_claim_pool,
-            retained_amount,
-            unallocated_remainder,
-            share_per_wallet,
-            eligible_count,
-            self.volcano_pressure,
-            self.get_next_eruption_threshold()
-        ));
+        let window = START_NS + TEST_LOCK_NS + MINUTE_NS;
+        withdraw(&mut contract, YOCTO_PER_NEAR, window);
 
-        let next_index = phase_index + 1;
-        if next_index >= PHASE_MILESTONES_YOCTO.len() {
-            env::log_str("ERUPTION_PHASE_TABLE_END reached=phase_16 no_future_phase_promised");
-        }
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, window + MINUTE_NS);
+
+        let participant = contract.get_participant(accounts(1)).unwrap();
+        assert!(participant.active);
+        assert_eq!(participant.lock_started_at, START_NS);
+        assert_eq!(participant.lots.len(), 2);
+        assert_eq!(participant.lots[0].lock_started_at, START_NS);
+        assert_eq!(
+            participant.lots[0].amount,
+            locked(10 * YOCTO_PER_NEAR) - YOCTO_PER_NEAR
+        );
     }
 
-    fn add_or_update_participant(&mut self, account_id: AccountId, locked_position: u128) {
-        let now = env::block_timestamp();
+    #[test]
+    fn tpi_ledger_records_deployment_and_return_efficiency() {
+        let mut contract = setup_with_deposit();
+
+        let routed = 10 * YOCTO_PER_NEAR * TREASURY_BPS / BPS_DENOMINATOR;
+        let power = routed * TPI_FULL_MAX_DEPLOY_BPS / BPS_DENOMINATOR;
+        assert_eq!(contract.get_tpi_state().treasury_bucket, routed);
+        assert_eq!(contract.get_tpi_deployment_power(), power);
+
+        context("treasury.near".parse().unwrap(), 0, START_NS + MINUTE_NS);
+        let id = contract.tpi_record_deployment(power, "venue".to_string(), "memo".to_string());
+        assert_eq!(id, 1);
+
+        let state = contract.get_tpi_state();
+        assert_eq!(state.open_deployed, power);
+        assert_eq!(state.treasury_bucket, routed - power);
+
+        let returned = power * 11 / 10;
+        contract.tpi_record_return(id, returned);
+
+        let deployment = contract.get_tpi_deployment(id).unwrap();
+        assert!(deployment.closed);
+        assert_eq!(deployment.returned_amount, returned);
+        assert_eq!(deployment.efficiency_bps, 11_000);
+        assert_eq!(deployment.returned_at, Some(START_NS + MINUTE_NS));
+
+        let state = contract.get_tpi_state();
+        assert_eq!(state.open_deployed, 0);
+        assert_eq!(state.treasury_bucket, routed - power + returned);
+        assert_eq!(state.sample_count, 1);
+        assert_eq!(state.average_efficiency_bps, 11_000);
+    }
 
-        match self.participant_index(&account_id) {
-            Some(index) => {
-                self.participants[index].active = true;
-                self.participants[index].position_balance = self.participants[index]
-                    .position_balance
-                    .checked_add(locked_position)
-                    .expect("Position overflow");
+    #[test]
+    #[should_panic(expected = "Amount exceeds TPI deployment power")]
+    fn tpi_deployment_cannot_exceed_power() {
+        let mut contract = setup_with_deposit();
 
-                self.participants[index].total_deposited = self.participants[index]
-                    .total_deposited
-                    .checked_add(locked_position)
-                    .expect("Deposit total overflow");
-            }
-            None => {
-                self.participants.push(Participant {
-                    account_id,
-                    active: true,
-                    position_balance: locked_position,
-                    total_deposited: locked_position,
-                    created_at: now,
-                    lock_started_at: now,
-                    claimed_eruption_ids: Vec::new(),
-                });
-            }
-        }
+        context(accounts(0), 0, START_NS + MINUTE_NS);
+        let power = contract.get_tpi_deployment_power();
+        contract.tpi_record_deployment(power + 1, "venue".to_string(), String::new());
     }
 
-    fn participant_index(&self, account_id: &AccountId) -> Option<usize> {
-        self.participants
-            .iter()
-            .position(|p| &p.account_id == account_id)
+    #[test]
+    #[should_panic(expected = "Deployment already closed")]
+    fn tpi_deployment_closes_once() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(0), 0, START_NS + MINUTE_NS);
+        let id = contract.tpi_record_deployment(1_000, "venue".to_string(), String::new());
+        contract.tpi_record_return(id, 1_000);
+        contract.tpi_record_return(id, 1_000);
     }
 
-    fn eruption_index(&self, eruption_id: u64) -> Option<usize> {
-        self.eruptions
-            .iter()
-            .position(|e| e.eruption_id == eruption_id)
+    fn close_tpi_sample(contract: &mut Contract, efficiency_bps: u128) {
+        context(accounts(0), 0, START_NS + MINUTE_NS);
+        let id = contract.tpi_record_deployment(10_000, "venue".to_string(), String::new());
+        contract.tpi_record_return(id, efficiency_bps);
     }
 
-    fn eligible_accounts(&self) -> Vec<AccountId> {
-        self.participants
-            .iter()
-            .filter(|p| {
-                p.active && p.position_balance >= MIN_ELIGIBLE_POSITION_YOCTO
-            })
-            .map(|p| p.account_id.clone())
-            .collect()
+    #[test]
+    fn tpi_thresholds_classify_average_efficiency() {
+        let mut contract = setup_with_deposit();
+
+        close_tpi_sample(&mut contract, 10_000);
+        let state = contract.get_tpi_state();
+        assert_eq!(state.status, TpiStatus::Full);
+        assert_eq!(state.max_deploy_bps, TPI_FULL_MAX_DEPLOY_BPS);
+
+        // Averages 9_000: the lowest efficiency still allowed to deploy.
+        close_tpi_sample(&mut contract, 8_000);
+        let state = contract.get_tpi_state();
+        assert_eq!(state.status, TpiStatus::Reduced);
+        assert_eq!(state.max_deploy_bps, TPI_REDUCED_MAX_DEPLOY_BPS);
+        assert_eq!(
+            state.deployment_power,
+            state.treasury_bucket * TPI_REDUCED_MAX_DEPLOY_BPS / BPS_DENOMINATOR
+        );
+        assert!(get_logs().iter().any(|log| {
+            log.contains("\"event\":\"tpi_status_updated\"")
+                && log.contains("\"previous\":\"FULL\"")
+                && log.contains("\"status\":\"REDUCED\"")
+        }));
+
+        close_tpi_sample(&mut contract, 8_999);
+        let state = contract.get_tpi_state();
+        assert_eq!(state.average_efficiency_bps, 8_999);
+        assert_eq!(state.status, TpiStatus::Frozen);
+        assert_eq!(state.deployment_power, 0);
     }
 
-    fn is_exit_window_open_for(&self, participant: &Participant, now: u64) -> bool {
-        if !participant.active {
-            return false;
-        }
+    #[test]
+    fn tpi_trend_compares_the_last_two_windows() {
+        let mut contract = setup_with_deposit();
 
-        if now < participant.lock_started_at {
-            return false;
+        for _ in 0..TPI_TREND_WINDOW {
+            close_tpi_sample(&mut contract, 10_000);
         }
-
-        let elapsed = now - participant.lock_started_at;
-
-        if elapsed < self.lock_duration_ns {
-            return false;
+        for _ in 0..TPI_TREND_WINDOW {
+            close_tpi_sample(&mut contract, 9_500);
         }
 
-        let cycle = self
-            .lock_duration_ns
-            .checked_add(self.exit_window_ns)
-            .expect("Cycle overflow");
-
-        let offset = (elapsed - self.lock_duration_ns) % cycle;
-
-        offset < self.exit_window_ns
+        let state = contract.get_tpi_state();
+        assert_eq!(state.status, TpiStatus::Reduced);
+        assert_eq!(state.sample_count, TPI_TREND_WINDOW as u64);
+        assert_eq!(state.average_efficiency_bps, 9_500);
+        assert_eq!(state.previous_average_efficiency_bps, Some(10_000));
+        assert_eq!(state.trend_delta_bps, -500);
     }
 
-    fn phase_threshold_by_index(&self, phase_index: usize) -> Option<u128> {
-        if phase_index < PHASE_MILESTONES_YOCTO.len() {
-            return Some(PHASE_MILESTONES_YOCTO[phase_index]);
-        }
+    #[test]
+    #[should_panic(expected = "TPI deployment frozen")]
+    fn frozen_tpi_rejects_new_deployments() {
+        let mut contract = setup();
+        freeze_tpi(&mut contract);
 
-        None
+        contract.tpi_record_deployment(1, "venue".to_string(), String::new());
     }
 
-    pub fn get_system_status(&self) -> SystemStatus {
-        SystemStatus {
-            project_status: "DEVELOPMENT_STAGE".to_string(),
-            website_status: "PUBLIC".to_string(),
-            wallet_status: "PENDING_UNTIL_VERIFIED".to_string(),
-            contract_status: "SOURCE_PRESENT_NOT_PUBLIC_FUND_LAUNCH_VERIFIED".to_string(),
-            treasury_status: "PENDING_PUBLIC_VERIFICATION".to_string(),
-            eruption_engine_status: "IMPLEMENTED_AS_SNAPSHOT_CLAIM_MODEL_PENDING_AUDIT".to_string(),
-            oim_status: "SPECIFIED_PENDING_RUNTIME_VERIFICATION".to_string(),
-            tpi_status: "SPECIFIED_PENDING_RUNTIME_VERIFICATION".to_string(),
-            public_fund_launch_status: "NOT_APPROVED_BEFORE_AUDIT_SOURCE_WASM_CONTRACT_AND_LEGAL_VERIFICATION".to_string(),
-        }
+    fn verify_everything(contract: &mut Contract) {
+        context(accounts(0), 0, START_NS);
+        contract.set_verification_flags(VerificationFlags {
+            website_public: true,
+            wallet_connection_verified: true,
+            contract_source_verified: true,
+            treasury_wallets_verified: true,
+        });
     }
 
-    pub fn get_owner(&self) -> AccountId {
-        self.owner_id.clone()
+    #[test]
+    fn system_status_derives_from_flags_and_pauses() {
+        let mut contract = setup();
+
+        let status = contract.get_system_status();
+        assert_eq!(status.project_status, "DEVELOPMENT_STAGE");
+        assert_eq!(status.website_status, "PUBLIC");
+        assert_eq!(status.wallet_status, "PENDING_UNTIL_VERIFIED");
+        assert_eq!(status.contract_status, "SOURCE_PRESENT_NOT_PUBLIC_FUND_LAUNCH_VERIFIED");
+        assert_eq!(status.treasury_status, "PENDING_PUBLIC_VERIFICATION");
+        assert_eq!(
+            status.eruption_engine_status,
+            "IMPLEMENTED_AS_SNAPSHOT_CLAIM_MODEL_PENDING_AUDIT"
+        );
+        assert_eq!(status.oim_status, "SPECIFIED_PENDING_RUNTIME_VERIFICATION");
+        assert_eq!(status.tpi_status, "RUNTIME_FULL");
+        assert_eq!(status.codes.oim_status, StatusCode::Specified);
+        assert_eq!(status.codes.tpi_status, StatusCode::Full);
+        assert_eq!(status.codes.public_fund_launch_status, StatusCode::NotApproved);
+
+        verify_everything(&mut contract);
+        let status = contract.get_system_status();
+        assert_eq!(status.wallet_status, "VERIFIED");
+        assert_eq!(status.contract_status, "SOURCE_VERIFIED_NOT_PUBLIC_FUND_LAUNCH");
+        assert_eq!(status.treasury_status, "VERIFIED");
+        assert_eq!(status.oim_status, "SPECIFIED_NOT_ENFORCED_BY_VERIFIED_SOURCE");
+        assert_eq!(status.codes.contract_status, StatusCode::Verified);
+
+        contract.set_pause_flags(false, false, true);
+        let status = contract.get_system_status();
+        assert_eq!(status.contract_status, "PAUSED_BY_OWNER");
+        assert_eq!(status.eruption_engine_status, "CLAIMS_PAUSED_BY_OWNER");
+        assert_eq!(status.oim_status, "SPECIFIED_PAUSED_BY_OWNER");
+        assert_eq!(status.codes.oim_status, StatusCode::Paused);
+        assert_eq!(contract.get_oim_status(), status.oim_status);
+
+        contract.set_pause_flags(false, false, false);
+        let status = contract.get_system_status();
+        assert_eq!(status.contract_status, "SOURCE_VERIFIED_NOT_PUBLIC_FUND_LAUNCH");
     }
 
-    pub fn get_operations_wallet(&self) -> AccountId {
-        self.operations_wallet.clone()
-    }
+    #[test]
+    fn frozen_tpi_shows_in_treasury_and_tpi_status() {
+        let mut contract = setup();
+        freeze_tpi(&mut contract);
+        verify_everything(&mut contract);
 
-    pub fn get_treasury_wallet(&self) -> AccountId {
-        self.treasury_wallet.clone()
+        let status = contract.get_system_status();
+        assert_eq!(status.treasury_status, "VERIFIED_DEPLOYMENT_FROZEN");
+        assert_eq!(status.tpi_status, "RUNTIME_FROZEN");
+        assert_eq!(status.codes.treasury_status, StatusCode::Frozen);
     }
 
-    pub fn get_growth_wallet(&self) -> AccountId {
-        self.growth_wallet.clone()
-    }
+    #[test]
+    #[should_panic(expected = "Deposits paused")]
+    fn paused_deposits_are_rejected() {
+        let mut contract = setup();
 
-    pub fn get_reserve_wallet(&self) -> AccountId {
-        self.reserve_wallet.clone()
-    }
+        context(accounts(0), 0, START_NS);
+        contract.set_pause_flags(true, false, false);
 
-    pub fn get_test_mode(&self) -> bool {
-        self.test_mode
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
     }
 
-    pub fn get_lock_duration_ns(&self) -> u64 {
-        self.lock_duration_ns
-    }
+    #[test]
+    #[should_panic(expected = "Withdrawals paused")]
+    fn paused_withdrawals_are_rejected() {
+        let mut contract = setup_with_deposit();
 
-    pub fn get_exit_window_ns(&self) -> u64 {
-        self.exit_window_ns
-    }
+        context(accounts(0), 0, START_NS);
+        contract.set_pause_flags(false, true, false);
 
-    pub fn get_system_fee_bps(&self) -> u128 {
-        SYSTEM_FEE_BPS
+        withdraw(&mut contract, YOCTO_PER_NEAR, START_NS + TEST_LOCK_NS + MINUTE_NS);
     }
 
-    pub fn get_fee_split_bps(&self) -> (u128, u128, u128, u128, u128) {
-        (
-            TREASURY_BPS,
-            GROWTH_BPS,
-            VOLCANO_BPS,
-            RESERVE_BPS,
-            CORE_OPS_BPS,
-        )
+    fn advance_to_audited(contract: &mut Contract) {
+        context(accounts(0), 0, START_NS);
+        contract.record_audit_attestation(
+            "auditor".to_string(),
+            "a".repeat(SHA256_HEX_LEN),
+            "contract".to_string(),
+        );
+        contract.advance_to_audited(
+            "c".repeat(SHA256_HEX_LEN),
+            "d".repeat(GIT_COMMIT_HEX_LEN),
+            "a".repeat(SHA256_HEX_LEN),
+        );
     }
 
-    pub fn get_volcano_pressure(&self) -> u128 {
-        self.volcano_pressure
-    }
+    #[test]
+    #[should_panic(expected = "Account not allowlisted before public launch")]
+    fn unlisted_account_cannot_deposit_before_public_launch() {
+        let mut contract = setup();
 
-    pub fn get_next_eruption_threshold(&self) -> u128 {
-        match self.phase_threshold_by_index(self.eruption_count as usize) {
-            Some(value) => value,
-            None => 0,
-        }
+        context(accounts(2), 10 * YOCTO_PER_NEAR, START_NS);
+        contract.deposit();
     }
 
-    pub fn get_current_phase_number(&self) -> u64 {
-        self.eruption_count + 1
-    }
+    #[test]
+    #[should_panic(expected = "Audit report hash has no recorded attestation")]
+    fn audited_stage_requires_a_recorded_attestation() {
+        let mut contract = setup();
 
-    pub fn get_phase_milestones(&self) -> Vec<u128> {
-        PHASE_MILESTONES_YOCTO.to_vec()
+        contract.advance_to_audited(
+            "c".repeat(SHA256_HEX_LEN),
+            "d".repeat(GIT_COMMIT_HEX_LEN),
+            "a".repeat(SHA256_HEX_LEN),
+        );
     }
 
-    pub fn get_eruption_count(&self) -> u64 {
-        self.eruption_count
-    }
+    #[test]
+    #[should_panic(expected = "Launch stage must be AUDITED")]
+    fn public_stage_cannot_skip_the_audit() {
+        let mut contract = setup();
+        verify_everything(&mut contract);
 
-    pub fn get_created_record_count(&self) -> u64 {
-        self.created_record_count
+        contract.advance_to_public("volcano.near".parse().unwrap(), "https://x".to_string());
     }
 
-    pub fn get_participant_count(&self) -> u64 {
-        self.participants.len() as u64
+    #[test]
+    #[should_panic(expected = "Contract source not verified")]
+    fn public_stage_requires_every_verification_flag() {
+        let mut contract = setup();
+        advance_to_audited(&mut contract);
+
+        contract.set_verification_flags(VerificationFlags {
+            website_public: true,
+            wallet_connection_verified: true,
+            contract_source_verified: false,
+            treasury_wallets_verified: true,
+        });
+        contract.advance_to_public("volcano.near".parse().unwrap(), "https://x".to_string());
     }
 
-    pub fn get_eligible_participant_count(&self) -> u64 {
-        self.eligible_accounts().len() as u64
-    }
+    #[test]
+    fn public_launch_opens_deposits_to_unlisted_accounts() {
+        let mut contract = setup();
+        advance_to_audited(&mut contract);
+        assert_eq!(contract.get_launch_stage(), LaunchStage::Audited);
+
+        // The allowlist still applies while audited.
+        let quote = contract.quote_deposit_impact(Some(accounts(2)), YOCTO_PER_NEAR);
+        assert_eq!(
+            quote.rejection_reason.as_deref(),
+            Some("Account not allowlisted before public launch")
+        );
 
-    pub fn get_participants(&self) -> Vec<Participant> {
-        self.participants.clone()
+        verify_everything(&mut contract);
+        contract.advance_to_public("volcano.near".parse().unwrap(), "https://x".to_string());
+
+        let artifacts = contract.get_launch_artifacts();
+        assert_eq!(contract.get_launch_stage(), LaunchStage::Public);
+        assert_eq!(artifacts.audit_report_hash, Some("a".repeat(SHA256_HEX_LEN)));
+        assert_eq!(artifacts.public_at, Some(START_NS));
+        assert!(contract.quote_deposit_impact(None, YOCTO_PER_NEAR).deposit_allowed);
+
+        context(accounts(2), 10 * YOCTO_PER_NEAR, START_NS + MINUTE_NS);
+        contract.deposit();
+        assert_eq!(
+            contract.get_position_balance(accounts(2)),
+            locked(10 * YOCTO_PER_NEAR)
+        );
     }
 
-    pub fn get_participant(&self, account_id: AccountId) -> Option<Participant> {
-        self.participant_index(&account_id)
-            .map(|index| self.participants[index].clone())
+    #[test]
+    fn deposit_capacity_reports_the_binding_cap() {
+        let mut contract = setup_with_deposit();
+
+        let position = locked(10 * YOCTO_PER_NEAR);
+        let capacity = contract.get_deposit_capacity(accounts(1));
+        assert!(capacity.allowlisted);
+        assert_eq!(capacity.position_balance.0, position);
+        assert_eq!(capacity.max_position, Some(U128(100 * YOCTO_PER_NEAR)));
+        assert_eq!(capacity.account_remaining, Some(U128(100 * YOCTO_PER_NEAR - position)));
+        assert_eq!(capacity.total_locked_position.0, position);
+        assert_eq!(capacity.tvl_remaining, Some(U128(1_000 * YOCTO_PER_NEAR - position)));
+        assert_eq!(capacity.max_locked_position_addable, capacity.account_remaining);
+        assert_eq!(
+            capacity.max_gross_deposit,
+            Some(U128(
+                (100 * YOCTO_PER_NEAR - position) * BPS_DENOMINATOR
+                    / (BPS_DENOMINATOR - SYSTEM_FEE_BPS)
+            ))
+        );
+
+        context(accounts(0), 0, START_NS);
+        contract.set_tvl_cap(Some(20 * YOCTO_PER_NEAR));
+        let capacity = contract.get_deposit_capacity(accounts(1));
+        assert_eq!(capacity.tvl_remaining, Some(U128(20 * YOCTO_PER_NEAR - position)));
+        assert_eq!(capacity.max_locked_position_addable, capacity.tvl_remaining);
+
+        // Public launch lifts the per-account cap; the TVL cap still binds.
+        advance_to_audited(&mut contract);
+        verify_everything(&mut contract);
+        contract.advance_to_public("volcano.near".parse().unwrap(), "https://x".to_string());
+
+        let capacity = contract.get_deposit_capacity(accounts(1));
+        assert_eq!(capacity.max_position, None);
+        assert_eq!(capacity.account_remaining, None);
+        assert_eq!(capacity.max_locked_position_addable, capacity.tvl_remaining);
     }
 
-    pub fn get_position_balance(&self, account_id: AccountId) -> u128 {
-        self.participant_index(&account_id)
-            .map(|index| self.participants[index].position_balance)
-            .unwrap_or(0)
+    #[test]
+    #[should_panic(expected = "Deposit exceeds per-account max position")]
+    fn deposit_over_account_cap_is_rejected() {
+        let mut contract = setup();
+        deposit(&mut contract, 90 * YOCTO_PER_NEAR, START_NS);
+        deposit(&mut contract, 20 * YOCTO_PER_NEAR, START_NS + MINUTE_NS);
     }
 
-    pub fn is_exit_window_open(&self, account_id: AccountId) -> bool {
-        match self.participant_index(&account_id) {
-            Some(index) => self.is_exit_window_open_for(
-                &self.participants[index],
-                env::block_timestamp(),
-            ),
-            None => false,
-        }
+    #[test]
+    #[should_panic(expected = "Deposit exceeds global TVL cap")]
+    fn deposit_over_tvl_cap_is_rejected() {
+        let mut contract = setup();
+
+        context(accounts(0), 0, START_NS);
+        contract.set_tvl_cap(Some(5 * YOCTO_PER_NEAR));
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
     }
 
-    pub fn get_eruption_snapshots(&self) -> Vec<EruptionSnapshot> {
-        self.eruptions.clone()
+    fn last_event(name: &str) -> near_sdk::serde_json::Value {
+        let log = get_logs()
+            .into_iter()
+            .rev()
+            .find(|log| log.contains(&format!("\"event\":\"{}\"", name)))
+            .expect("Event not logged");
+        let json = log.strip_prefix("EVENT_JSON:").expect("Not a NEP-297 log");
+        near_sdk::serde_json::from_str(json).unwrap()
     }
 
-    pub fn get_eruption_snapshot(&self, eruption_id: u64) -> Option<EruptionSnapshot> {
-        self.eruption_index(eruption_id)
-            .map(|index| self.eruptions[index].clone())
+    #[test]
+    fn deposit_and_withdraw_emit_nep297_payloads() {
+        let mut contract = setup_with_deposit();
+
+        let position = locked(10 * YOCTO_PER_NEAR);
+        let volcano = 10 * YOCTO_PER_NEAR * VOLCANO_BPS / BPS_DENOMINATOR;
+
+        let event = last_event("deposit");
+        assert_eq!(event["standard"], "near_intersect_volcano");
+        assert_eq!(event["version"], "1.1.0");
+        assert_eq!(event["data"]["account_id"], accounts(1).to_string());
+        assert_eq!(event["data"]["amount"], (10 * YOCTO_PER_NEAR).to_string());
+        assert_eq!(event["data"]["locked_position"], position.to_string());
+        assert_eq!(event["data"]["volcano"], volcano.to_string());
+        assert_eq!(event["data"]["pressure"], volcano.to_string());
+        assert_eq!(event["data"]["participant_count"], 1);
+        assert_eq!(event["data"]["lot_id"], 1);
+        assert!(get_logs().iter().any(|log| log.starts_with("DEPOSIT caller=bob ")));
+
+        withdraw(&mut contract, YOCTO_PER_NEAR, START_NS + TEST_LOCK_NS + MINUTE_NS);
+
+        let event = last_event("withdraw_position");
+        assert_eq!(event["data"]["gross"], YOCTO_PER_NEAR.to_string());
+        assert_eq!(event["data"]["remaining_position"], (position - YOCTO_PER_NEAR).to_string());
+        assert_eq!(event["data"]["consumed_lots"][0]["lot_id"], 1);
+        assert_eq!(event["data"]["consumed_lots"][0]["amount"], YOCTO_PER_NEAR.to_string());
     }
 
-    pub fn has_claimed_eruption(&self, account_id: AccountId, eruption_id: u64) -> bool {
-        match self.participant_index(&account_id) {
-            Some(index) => self.participants[index]
-                .claimed_eruption_ids
-                .contains(&eruption_id),
-            None => false,
-        }
+    #[test]
+    fn legacy_log_lines_can_be_turned_off() {
+        let mut contract = setup();
+
+        context(accounts(0), 0, START_NS);
+        contract.set_legacy_logs_enabled(false);
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+
+        let logs = get_logs();
+        assert!(logs.iter().any(|log| log.contains("\"event\":\"deposit\"")));
+        assert!(logs.iter().all(|log| log.starts_with("EVENT_JSON:")));
     }
 
-    pub fn get_create_record_fee_yocto(&self) -> u128 {
-        CREATE_RECORD_FEE_YOCTO
+    fn folded(head: [u8; 32], effect: &StateEffect) -> [u8; 32] {
+        let mut preimage = head.to_vec();
+        preimage.extend_from_slice(&borsh::to_vec(effect).unwrap());
+        env::sha256_array(&preimage)
     }
 
-    pub fn get_min_eligible_position_yocto(&self) -> u128 {
-        MIN_ELIGIBLE_POSITION_YOCTO
+    #[test]
+    fn state_hash_chain_folds_each_effect_in_order() {
+        let mut contract = setup();
+        assert_eq!(contract.get_state_hash_chain().head, "0".repeat(64));
+        assert_eq!(contract.get_state_hash_chain().length, 0);
+
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS);
+
+        let after_deposit = folded(
+            [0; 32],
+            &StateEffect::Deposit {
+                account_id: accounts(1),
+                locked_position: locked(10 * YOCTO_PER_NEAR),
+                volcano: 10 * YOCTO_PER_NEAR * VOLCANO_BPS / BPS_DENOMINATOR,
+                at: START_NS,
+            },
+        );
+        assert_eq!(contract.get_state_hash_chain().head, to_hex(&after_deposit));
+
+        let window = START_NS + TEST_LOCK_NS + MINUTE_NS;
+        withdraw(&mut contract, YOCTO_PER_NEAR, window);
+
+        let after_withdraw = folded(
+            after_deposit,
+            &StateEffect::WithdrawPosition {
+                account_id: accounts(1),
+                gross: YOCTO_PER_NEAR,
+                volcano: YOCTO_PER_NEAR * VOLCANO_BPS / BPS_DENOMINATOR,
+                at: window,
+            },
+        );
+        let chain = contract.get_state_hash_chain();
+        assert_eq!(chain.head, to_hex(&after_withdraw));
+        assert_eq!(chain.length, 2);
     }
 
-    pub fn get_oim_status(&self) -> String {
-        "SPECIFIED_PENDING_RUNTIME_VERIFICATION".to_string()
+    // The indexer replays this same sequence from its logs and must reach the
+    // same head; keep the two constants in step.
+    #[test]
+    fn state_hash_chain_head_for_the_indexer_fixture() {
+        let mut contract = setup_with_deposit();
+
+        context(accounts(1), CREATE_RECORD_FEE_YOCTO, START_NS + MINUTE_NS);
+        contract.create_volcano_record();
+
+        withdraw(&mut contract, YOCTO_PER_NEAR, START_NS + TEST_LOCK_NS + MINUTE_NS);
+
+        let chain = contract.get_state_hash_chain();
+        assert_eq!(chain.length, 3);
+        assert_eq!(chain.head, "2965eaa0676ac384bb37b37dee21c77c74ce07116381fe76314b78faa53eb252");
     }
 
-    pub fn get_tpi_status(&self) -> String {
-        "SPECIFIED_PENDING_RUNTIME_VERIFICATION".to_string()
+    #[test]
+    fn withdrawal_consumes_open_lots_oldest_first_and_splits_the_last() {
+        let mut contract = setup_with_deposit();
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS + MINUTE_NS);
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS + TEST_LOCK_NS);
+
+        // The first two lots are in their exit window; the third is locked.
+        let now = START_NS + TEST_LOCK_NS + 2 * MINUTE_NS;
+        let lot = locked(10 * YOCTO_PER_NEAR);
+        context(accounts(1), 0, now);
+        assert_eq!(contract.get_unlocked_amount(accounts(1)), 2 * lot);
+
+        let consumed = contract.consume_unlocked_lots(0, lot + lot / 2, now);
+        assert_eq!(consumed.len(), 2);
+        assert_eq!((consumed[0].lot_id, consumed[0].amount.0), (1, lot));
+        assert_eq!((consumed[1].lot_id, consumed[1].amount.0), (2, lot / 2));
+
+        let lots = contract.get_position_lots(accounts(1));
+        assert_eq!(lots.len(), 2);
+        assert_eq!((lots[0].lot_id, lots[0].amount.0), (2, lot - lot / 2));
+        assert_eq!(lots[0].original_amount.0, lot);
+        assert_eq!((lots[1].lot_id, lots[1].amount.0), (3, lot));
     }
 
-    pub fn canonical_law_summary(&self) -> String {
-        "NEAR_INTERSECT_VOLCANO_CANONICAL_PRE_LAUNCH_LAW: development_stage; 5_percent_fee; treasury_1_40_growth_0_90_volcano_1_05_reserve_0_55_core_ops_1_10; production_lock_2_years; exit_window_7_days; test_mode_2_hours_7_minutes; no_manual_eruption; explicit_phase_table; phase_1_75_25; phase_2_plus_60_40; eligible_active_wallet_snapshot_claims; local_profiles_no_financial_rights; OIM_TPI_pending_runtime_verification; no_guaranteed_returns".to_string()
+    #[test]
+    #[should_panic(expected = "Amount exceeds unlocked lots")]
+    fn withdrawal_cannot_reach_into_locked_lots() {
+        let mut contract = setup_with_deposit();
+        deposit(&mut contract, 10 * YOCTO_PER_NEAR, START_NS + TEST_LOCK_NS);
+
+        let now = START_NS + TEST_LOCK_NS + MINUTE_NS;
+        context(accounts(1), 0, now);
+        contract.consume_unlocked_lots(0, locked(10 * YOCTO_PER_NEAR) + 1, now);
     }
 }
@@ -0,0 +1,2043 @@
+//! Offline replay of `near_intersect_volcano` NEP-297 events.
+//!
+//! Input is a JSON array of receipts, each carrying its `block_timestamp` and the
+//! `logs` it produced, in execution order. Replay rebuilds participants, eruption
+//! snapshots and volcano pressure without any RPC access, and the result can be
+//! diffed against `get_participants` / `get_eruption_snapshots` dumps, and the
+//! recomputed state hash chain against `get_state_hash_chain`.
+
+use borsh::BorshSerialize;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+pub const EVENT_STANDARD: &str = "near_intersect_volcano";
+pub const EVENT_LOG_PREFIX: &str = "EVENT_JSON:";
+
+// Mirrors the contract constant; eruption eligibility is recomputed from it.
+pub const MIN_ELIGIBLE_POSITION_YOCTO: u128 = 1_000_000_000_000_000_000_000_000;
+
+pub const EQUAL_SHARE: &str = "EQUAL_SHARE";
+pub const POSITION_WEIGHTED: &str = "POSITION_WEIGHTED";
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Json(serde_json::Error),
+    MalformedEvent {
+        receipt: usize,
+        log: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Json(err) => write!(f, "invalid JSON input: {}", err),
+            ReplayError::MalformedEvent {
+                receipt,
+                log,
+                reason,
+            } => write!(
+                f,
+                "malformed event at receipt {} log {}: {}",
+                receipt, log, reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(err: serde_json::Error) -> Self {
+        ReplayError::Json(err)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Receipt {
+    #[serde(default)]
+    pub receipt_id: Option<String>,
+    #[serde(deserialize_with = "u64_flex")]
+    pub block_timestamp: u64,
+    #[serde(default)]
+    pub logs: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Participant {
+    pub account_id: String,
+    pub active: bool,
+    pub position_balance: u128,
+    pub total_deposited: u128,
+    pub created_at: u64,
+    pub lock_started_at: u64,
+    pub claimed_eruption_ids: Vec<u64>,
+    pub lots: Vec<PositionLot>,
+    pub next_lot_id: u64,
+    #[serde(default)]
+    pub auto_compound: bool,
+    #[serde(default)]
+    pub claim_agents: Vec<String>,
+    // `last_active_at` and pending inheritance claims are not mirrored: most
+    // participant calls that refresh them emit no event of their own.
+    #[serde(default)]
+    pub beneficiary_id: Option<String>,
+    #[serde(default)]
+    pub inactivity_period_ns: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionLot {
+    pub lot_id: u64,
+    pub amount: u128,
+    pub original_amount: u128,
+    pub lock_started_at: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EruptionSnapshot {
+    pub eruption_id: u64,
+    pub phase_number: u64,
+    pub threshold: u128,
+    pub distribution_pool: u128,
+    pub retained_amount: u128,
+    pub unallocated_remainder: u128,
+    pub share_per_wallet: u128,
+    pub eligible_count: u64,
+    pub claimed_count: u64,
+    pub created_at: u64,
+    pub claim_deadline: u64,
+    pub eligible_accounts: Vec<String>,
+    #[serde(default)]
+    pub swept: bool,
+    #[serde(default = "equal_share")]
+    pub distribution_mode: String,
+    #[serde(default)]
+    pub eligible_shares: Vec<u128>,
+    #[serde(default)]
+    pub claimed_amount: u128,
+    #[serde(default)]
+    pub claim_window_ns: u64,
+}
+
+impl EruptionSnapshot {
+    pub fn share_of(&self, account_id: &str) -> u128 {
+        match self.eligible_accounts.iter().position(|a| a == account_id) {
+            Some(index) if self.distribution_mode == POSITION_WEIGHTED => {
+                self.eligible_shares.get(index).copied().unwrap_or(0)
+            }
+            Some(_) => self.share_per_wallet,
+            None => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DepositEvent {
+    pub account_id: String,
+    #[serde(deserialize_with = "u128_string")]
+    pub locked_position: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub volcano: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub pressure: u128,
+    pub lot_id: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RecordCreatedEvent {
+    pub creator: String,
+    #[serde(deserialize_with = "u128_string")]
+    pub pressure_added: u128,
+    pub record_number: u64,
+    #[serde(deserialize_with = "u128_string")]
+    pub pressure: u128,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WithdrawPositionEvent {
+    pub account_id: String,
+    #[serde(deserialize_with = "u128_string")]
+    pub gross: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub volcano: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub remaining_position: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub pressure: u128,
+    pub consumed_lots: Vec<LotConsumption>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EarlyExitEvent {
+    pub account_id: String,
+    #[serde(deserialize_with = "u128_string")]
+    pub gross: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub volcano: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub penalty: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub remaining_position: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub pressure: u128,
+    pub consumed_lots: Vec<LotConsumption>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LotConsumption {
+    pub lot_id: u64,
+    #[serde(deserialize_with = "u128_string")]
+    pub amount: u128,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EruptionSnapshotEvent {
+    pub eruption_id: u64,
+    pub phase_number: u64,
+    #[serde(deserialize_with = "u128_string")]
+    pub threshold: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub allocated_distribution: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub retained: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub unallocated_remainder: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub share_per_wallet: u128,
+    pub eligible_count: u64,
+    #[serde(deserialize_with = "u128_string")]
+    pub carried_pressure: u128,
+    pub claim_deadline: u64,
+    #[serde(default = "equal_share")]
+    pub distribution_mode: String,
+    #[serde(default, deserialize_with = "u128_string_vec")]
+    pub eligible_shares: Vec<u128>,
+    // Absent before 1.3.0, when the deadline followed the exit window.
+    #[serde(default)]
+    pub claim_window_ns: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EruptionProgressEvent {
+    pub phase_number: u64,
+    pub as_of: u64,
+    pub participant_count: u64,
+    pub scanned_from: u64,
+    pub scanned_to: u64,
+    pub eligible_accounts: Vec<String>,
+    #[serde(default, deserialize_with = "u128_string_vec")]
+    pub eligible_positions: Vec<u128>,
+    #[serde(default)]
+    pub min_holding_age_ns: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EruptionClaimsSweptEvent {
+    pub eruption_id: u64,
+    pub unclaimed_count: u64,
+    #[serde(deserialize_with = "u128_string")]
+    pub amount: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub pressure: u128,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EruptionClaimsRolledBackEvent {
+    pub account_id: String,
+    pub claims: Vec<ClaimedShare>,
+    #[serde(deserialize_with = "u128_string")]
+    pub returned_to_pressure: u128,
+    #[serde(deserialize_with = "u128_string")]
+    pub pressure: u128,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClaimedShare {
+    pub eruption_id: u64,
+    #[serde(deserialize_with = "u128_string")]
+    pub share: u128,
+}
+
+// Payload of `eligibility_policy_updated`; public keys stay in NEAR's
+// `ed25519:<base58>` string form.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EligibilityPolicy {
+    pub min_account_age_ns: u64,
+    pub attester_public_key: Option<String>,
+    pub attestation_required: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EligibilityAttestation {
+    pub account_id: String,
+    pub attester_public_key: String,
+    pub expires_at: u64,
+    pub submitted_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EruptionClaimedEvent {
+    pub account_id: String,
+    pub eruption_id: u64,
+    #[serde(deserialize_with = "u128_string")]
+    pub share: u128,
+    pub claimed_count: u64,
+    #[serde(default)]
+    pub compounded_lot_id: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AutoCompoundUpdatedEvent {
+    pub account_id: String,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BeneficiaryUpdatedEvent {
+    pub account_id: String,
+    pub beneficiary_id: Option<String>,
+    pub inactivity_period_ns: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClaimAgentUpdatedEvent {
+    pub account_id: String,
+    pub agent_id: String,
+    pub authorized: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum VolcanoEvent {
+    Deposit(DepositEvent),
+    RecordCreated(RecordCreatedEvent),
+    WithdrawPosition(WithdrawPositionEvent),
+    EarlyExit(EarlyExitEvent),
+    EruptionProgress(EruptionProgressEvent),
+    EruptionSnapshot(EruptionSnapshotEvent),
+    EruptionClaimed(EruptionClaimedEvent),
+    EruptionClaimsSwept(EruptionClaimsSweptEvent),
+    EruptionClaimsRolledBack(EruptionClaimsRolledBackEvent),
+    EligibilityPolicyUpdated(EligibilityPolicy),
+    EligibilityAttested(EligibilityAttestation),
+    AutoCompoundUpdated(AutoCompoundUpdatedEvent),
+    ClaimAgentUpdated(ClaimAgentUpdatedEvent),
+    BeneficiaryUpdated(BeneficiaryUpdatedEvent),
+    // Administrative and informational events do not change replayed state.
+    Other(String),
+}
+
+impl VolcanoEvent {
+    fn is_primary(&self) -> bool {
+        matches!(
+            self,
+            VolcanoEvent::Deposit(_)
+                | VolcanoEvent::RecordCreated(_)
+                | VolcanoEvent::WithdrawPosition(_)
+                | VolcanoEvent::EarlyExit(_)
+                | VolcanoEvent::EruptionClaimsSwept(_)
+                | VolcanoEvent::EruptionClaimsRolledBack(_)
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct EventEnvelope {
+    standard: String,
+    version: String,
+    event: String,
+    #[serde(default)]
+    data: Value,
+}
+
+/// Parses one log line. Returns `Ok(None)` for legacy lines and other standards.
+pub fn parse_event(log: &str) -> Result<Option<VolcanoEvent>, String> {
+    let json = match log.strip_prefix(EVENT_LOG_PREFIX) {
+        Some(json) => json,
+        None => return Ok(None),
+    };
+
+    let envelope: EventEnvelope = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+    if envelope.standard != EVENT_STANDARD {
+        return Ok(None);
+    }
+
+    if !envelope.version.starts_with("1.") {
+        return Err(format!(
+            "unsupported {} version {}",
+            envelope.event, envelope.version
+        ));
+    }
+
+    let data = envelope.data;
+    let event = match envelope.event.as_str() {
+        "deposit" => VolcanoEvent::Deposit(from_data(data)?),
+        "record_created" => VolcanoEvent::RecordCreated(from_data(data)?),
+        "withdraw_position" => VolcanoEvent::WithdrawPosition(from_data(data)?),
+        "early_exit" => VolcanoEvent::EarlyExit(from_data(data)?),
+        "eruption_progress" => VolcanoEvent::EruptionProgress(from_data(data)?),
+        "eruption_claims_rolled_back" => VolcanoEvent::EruptionClaimsRolledBack(from_data(data)?),
+        "eligibility_policy_updated" => VolcanoEvent::EligibilityPolicyUpdated(from_data(data)?),
+        "eligibility_attested" => VolcanoEvent::EligibilityAttested(from_data(data)?),
+        "auto_compound_updated" => VolcanoEvent::AutoCompoundUpdated(from_data(data)?),
+        "claim_agent_updated" => VolcanoEvent::ClaimAgentUpdated(from_data(data)?),
+        "beneficiary_updated" => VolcanoEvent::BeneficiaryUpdated(from_data(data)?),
+        "eruption_claims_swept" => VolcanoEvent::EruptionClaimsSwept(from_data(data)?),
+        "eruption_snapshot" => VolcanoEvent::EruptionSnapshot(from_data(data)?),
+        "eruption_claimed" => VolcanoEvent::EruptionClaimed(from_data(data)?),
+        other => VolcanoEvent::Other(other.to_string()),
+    };
+
+    Ok(Some(event))
+}
+
+fn from_data<T: for<'de> Deserialize<'de>>(data: Value) -> Result<T, String> {
+    serde_json::from_value(data).map_err(|e| e.to_string())
+}
+
+// Mirrors the contract's `StateEffect`; variant and field order are part of the
+// borsh encoding and must match exactly.
+#[derive(BorshSerialize)]
+enum StateEffect {
+    Deposit {
+        account_id: String,
+        locked_position: u128,
+        volcano: u128,
+        at: u64,
+    },
+    RecordCreated {
+        creator: String,
+        pressure_added: u128,
+        record_number: u64,
+        at: u64,
+    },
+    WithdrawPosition {
+        account_id: String,
+        gross: u128,
+        volcano: u128,
+        at: u64,
+    },
+    EruptionSnapshot {
+        eruption_id: u64,
+        phase_number: u64,
+        threshold: u128,
+        distribution_pool: u128,
+        retained_amount: u128,
+        unallocated_remainder: u128,
+        share_per_wallet: u128,
+        carried_pressure: u128,
+        claim_deadline: u64,
+        eligible_accounts: Vec<String>,
+        at: u64,
+    },
+    EruptionClaimed {
+        account_id: String,
+        eruption_id: u64,
+        share: u128,
+        at: u64,
+    },
+    EarlyExit {
+        account_id: String,
+        gross: u128,
+        volcano: u128,
+        penalty: u128,
+        at: u64,
+    },
+    ClaimsSwept {
+        eruption_id: u64,
+        amount: u128,
+        at: u64,
+    },
+    EruptionShares {
+        eruption_id: u64,
+        shares: Vec<u128>,
+    },
+    ClaimRolledBack {
+        account_id: String,
+        eruption_id: u64,
+        share: u128,
+        at: u64,
+    },
+}
+
+// An eligibility scan the contract has started but not yet finalized.
+#[derive(Clone, Debug, Serialize)]
+pub struct PendingEruption {
+    pub phase_number: u64,
+    pub as_of: u64,
+    pub min_holding_age_ns: u64,
+    pub participant_count: u64,
+    pub cursor: u64,
+    pub eligible_accounts: Vec<String>,
+    pub eligible_positions: Vec<u128>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ReplayState {
+    pub participants: Vec<Participant>,
+    pub eruptions: Vec<EruptionSnapshot>,
+    pub volcano_pressure: u128,
+    pub eruption_count: u64,
+    pub created_record_count: u64,
+    pub pending_eruption: Option<PendingEruption>,
+    pub eligibility_policy: EligibilityPolicy,
+    pub eligibility_attestations: Vec<EligibilityAttestation>,
+    pub events_applied: u64,
+    pub state_hash_head: String,
+    pub state_hash_length: u64,
+    pub inconsistencies: Vec<String>,
+    #[serde(skip)]
+    state_hash: [u8; 32],
+}
+
+impl ReplayState {
+    fn eligible_accounts(
+        &self,
+        range: std::ops::Range<usize>,
+        as_of: u64,
+        min_holding_age_ns: u64,
+    ) -> (Vec<String>, Vec<u128>) {
+        self.participants[range]
+            .iter()
+            .filter_map(|p| {
+                self.eligible_position(p, as_of, min_holding_age_ns)
+                    .map(|held| (p.account_id.clone(), held))
+            })
+            .unzip()
+    }
+
+    // Mirrors the contract: only lots held for `min_holding_age_ns` by `as_of`
+    // count, and the participant must pass the eligibility policy.
+    fn eligible_position(
+        &self,
+        participant: &Participant,
+        as_of: u64,
+        min_holding_age_ns: u64,
+    ) -> Option<u128> {
+        let held: u128 = participant
+            .lots
+            .iter()
+            .filter(|lot| lot.lock_started_at.saturating_add(min_holding_age_ns) <= as_of)
+            .map(|lot| lot.amount)
+            .sum();
+
+        if participant.active
+            && held >= MIN_ELIGIBLE_POSITION_YOCTO
+            && self.passes_eligibility_policy(participant, as_of)
+        {
+            Some(held)
+        } else {
+            None
+        }
+    }
+
+    fn passes_eligibility_policy(&self, participant: &Participant, as_of: u64) -> bool {
+        let policy = &self.eligibility_policy;
+
+        if participant
+            .created_at
+            .saturating_add(policy.min_account_age_ns)
+            > as_of
+        {
+            return false;
+        }
+
+        if !policy.attestation_required {
+            return true;
+        }
+
+        self.eligibility_attestations.iter().any(|a| {
+            a.account_id == participant.account_id
+                && Some(&a.attester_public_key) == policy.attester_public_key.as_ref()
+                && a.submitted_at <= as_of
+                && a.expires_at >= as_of
+        })
+    }
+
+    fn participant_mut(&mut self, account_id: &str) -> Option<&mut Participant> {
+        self.participants
+            .iter_mut()
+            .find(|p| p.account_id == account_id)
+    }
+
+    fn fold_state_effect(&mut self, effect: &StateEffect) {
+        let encoded = borsh::to_vec(effect).expect("effect encoding");
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.state_hash);
+        hasher.update(&encoded);
+
+        self.state_hash = hasher.finalize().into();
+        self.state_hash_length += 1;
+        self.state_hash_head = to_hex(&self.state_hash);
+    }
+
+    fn note(&mut self, receipt: usize, message: String) {
+        self.inconsistencies
+            .push(format!("receipt {}: {}", receipt, message));
+    }
+
+    // The contract logs each primary event before any eruption it triggers, so
+    // events replay in log order. A receipt may carry several primaries
+    // (batched scheduled withdrawals).
+    fn apply_receipt(&mut self, index: usize, receipt: &Receipt, events: &[VolcanoEvent]) {
+        for event in events {
+            self.apply_event(index, receipt.block_timestamp, event);
+        }
+    }
+
+    fn apply_event(&mut self, index: usize, now: u64, event: &VolcanoEvent) {
+        // Primary effects move pressure before the reported value is checked.
+        if event.is_primary() {
+            self.apply_primary(index, now, event);
+        }
+
+        match event {
+            VolcanoEvent::Deposit(e) => self.check_pressure(index, "deposit", e.pressure),
+            VolcanoEvent::RecordCreated(e) => {
+                self.check_pressure(index, "record_created", e.pressure)
+            }
+            VolcanoEvent::WithdrawPosition(e) => {
+                self.check_pressure(index, "withdraw_position", e.pressure)
+            }
+            VolcanoEvent::EarlyExit(e) => self.check_pressure(index, "early_exit", e.pressure),
+            VolcanoEvent::EruptionClaimsSwept(e) => {
+                self.check_pressure(index, "eruption_claims_swept", e.pressure)
+            }
+            VolcanoEvent::EruptionProgress(e) => self.apply_progress(index, e),
+            VolcanoEvent::EruptionSnapshot(e) => self.apply_snapshot(index, now, e),
+            VolcanoEvent::EruptionClaimed(e) => self.apply_claim(index, now, e),
+            VolcanoEvent::EruptionClaimsRolledBack(e) => {
+                self.check_pressure(index, "eruption_claims_rolled_back", e.pressure)
+            }
+            VolcanoEvent::EligibilityPolicyUpdated(policy) => {
+                self.eligibility_policy = policy.clone()
+            }
+            VolcanoEvent::EligibilityAttested(attestation) => {
+                self.eligibility_attestations
+                    .retain(|a| a.account_id != attestation.account_id);
+                self.eligibility_attestations.push(attestation.clone());
+            }
+            VolcanoEvent::AutoCompoundUpdated(e) => match self.participant_mut(&e.account_id) {
+                Some(p) => p.auto_compound = e.enabled,
+                None => {
+                    let message =
+                        format!("auto_compound_updated for unknown account {}", e.account_id);
+                    self.note(index, message);
+                }
+            },
+            VolcanoEvent::ClaimAgentUpdated(e) => match self.participant_mut(&e.account_id) {
+                Some(p) => {
+                    if !e.authorized {
+                        p.claim_agents.retain(|a| a != &e.agent_id);
+                    } else if !p.claim_agents.contains(&e.agent_id) {
+                        p.claim_agents.push(e.agent_id.clone());
+                    }
+                }
+                None => {
+                    let message =
+                        format!("claim_agent_updated for unknown account {}", e.account_id);
+                    self.note(index, message);
+                }
+            },
+            VolcanoEvent::BeneficiaryUpdated(e) => match self.participant_mut(&e.account_id) {
+                Some(p) => {
+                    p.beneficiary_id = e.beneficiary_id.clone();
+                    p.inactivity_period_ns = e.inactivity_period_ns;
+                }
+                None => {
+                    let message =
+                        format!("beneficiary_updated for unknown account {}", e.account_id);
+                    self.note(index, message);
+                }
+            },
+            VolcanoEvent::Other(_) => {}
+        }
+
+        self.events_applied += 1;
+    }
+
+    fn apply_primary(&mut self, index: usize, now: u64, event: &VolcanoEvent) {
+        match event {
+            VolcanoEvent::Deposit(e) => {
+                if self.participant_mut(&e.account_id).is_none() {
+                    self.participants.push(Participant {
+                        account_id: e.account_id.clone(),
+                        active: true,
+                        position_balance: 0,
+                        total_deposited: 0,
+                        created_at: now,
+                        lock_started_at: now,
+                        claimed_eruption_ids: Vec::new(),
+                        lots: Vec::new(),
+                        next_lot_id: 1,
+                        auto_compound: false,
+                        claim_agents: Vec::new(),
+                        beneficiary_id: None,
+                        inactivity_period_ns: 0,
+                    });
+                }
+
+                let p = self
+                    .participant_mut(&e.account_id)
+                    .expect("participant just ensured");
+
+                if !p.active {
+                    p.lock_started_at = now;
+                }
+
+                p.active = true;
+                p.position_balance += e.locked_position;
+                p.total_deposited += e.locked_position;
+
+                let lot_id = p.next_lot_id;
+                p.next_lot_id += 1;
+                p.lots.push(PositionLot {
+                    lot_id,
+                    amount: e.locked_position,
+                    original_amount: e.locked_position,
+                    lock_started_at: now,
+                });
+
+                if lot_id != e.lot_id {
+                    let message = format!(
+                        "deposit by {} opened lot {} but replay expected {}",
+                        e.account_id, e.lot_id, lot_id
+                    );
+                    self.note(index, message);
+                }
+
+                self.volcano_pressure += e.volcano;
+
+                self.fold_state_effect(&StateEffect::Deposit {
+                    account_id: e.account_id.clone(),
+                    locked_position: e.locked_position,
+                    volcano: e.volcano,
+                    at: now,
+                });
+            }
+            VolcanoEvent::RecordCreated(e) => {
+                self.created_record_count += 1;
+
+                if self.created_record_count != e.record_number {
+                    let message = format!(
+                        "record_created by {} numbered {} but replay expected {}",
+                        e.creator, e.record_number, self.created_record_count
+                    );
+                    self.note(index, message);
+                }
+
+                self.volcano_pressure += e.pressure_added;
+
+                self.fold_state_effect(&StateEffect::RecordCreated {
+                    creator: e.creator.clone(),
+                    pressure_added: e.pressure_added,
+                    record_number: e.record_number,
+                    at: now,
+                });
+            }
+            VolcanoEvent::WithdrawPosition(e) => {
+                self.apply_exit(
+                    index,
+                    "withdraw_position",
+                    &e.account_id,
+                    e.gross,
+                    e.remaining_position,
+                    &e.consumed_lots,
+                );
+
+                self.volcano_pressure += e.volcano;
+
+                self.fold_state_effect(&StateEffect::WithdrawPosition {
+                    account_id: e.account_id.clone(),
+                    gross: e.gross,
+                    volcano: e.volcano,
+                    at: now,
+                });
+            }
+            VolcanoEvent::EarlyExit(e) => {
+                self.apply_exit(
+                    index,
+                    "early_exit",
+                    &e.account_id,
+                    e.gross,
+                    e.remaining_position,
+                    &e.consumed_lots,
+                );
+
+                self.volcano_pressure += e.volcano + e.penalty;
+
+                self.fold_state_effect(&StateEffect::EarlyExit {
+                    account_id: e.account_id.clone(),
+                    gross: e.gross,
+                    volcano: e.volcano,
+                    penalty: e.penalty,
+                    at: now,
+                });
+            }
+            VolcanoEvent::EruptionClaimsSwept(e) => {
+                let expected = match self
+                    .eruptions
+                    .iter_mut()
+                    .find(|s| s.eruption_id == e.eruption_id)
+                {
+                    Some(s) if !s.swept && now > s.claim_deadline => {
+                        s.swept = true;
+                        let unclaimed = s.eligible_count - s.claimed_count;
+                        Some((unclaimed, s.distribution_pool - s.claimed_amount))
+                    }
+                    _ => None,
+                };
+
+                match expected {
+                    Some((unclaimed, amount))
+                        if unclaimed == e.unclaimed_count && amount == e.amount => {}
+                    Some((unclaimed, amount)) => {
+                        let message = format!(
+                            "eruption_claims_swept {} swept {} claims worth {} but replay expected {} worth {}",
+                            e.eruption_id, e.unclaimed_count, e.amount, unclaimed, amount
+                        );
+                        self.note(index, message);
+                    }
+                    None => {
+                        let message = format!(
+                            "eruption_claims_swept {} for an unknown, open or already swept eruption",
+                            e.eruption_id
+                        );
+                        self.note(index, message);
+                    }
+                }
+
+                self.volcano_pressure += e.amount;
+
+                self.fold_state_effect(&StateEffect::ClaimsSwept {
+                    eruption_id: e.eruption_id,
+                    amount: e.amount,
+                    at: now,
+                });
+            }
+            VolcanoEvent::EruptionClaimsRolledBack(e) => self.apply_rollback(index, now, e),
+            _ => {}
+        }
+    }
+
+    fn apply_rollback(&mut self, index: usize, now: u64, e: &EruptionClaimsRolledBackEvent) {
+        let mut returned_to_pressure = 0;
+
+        for claim in &e.claims {
+            let reopened = match self.participant_mut(&e.account_id) {
+                Some(p) if p.claimed_eruption_ids.contains(&claim.eruption_id) => {
+                    p.claimed_eruption_ids.retain(|id| *id != claim.eruption_id);
+                    true
+                }
+                _ => false,
+            };
+
+            match self
+                .eruptions
+                .iter_mut()
+                .find(|s| s.eruption_id == claim.eruption_id)
+            {
+                Some(s) if reopened && s.claimed_amount >= claim.share => {
+                    s.claimed_count -= 1;
+                    s.claimed_amount -= claim.share;
+                    if s.swept {
+                        returned_to_pressure += claim.share;
+                    }
+                }
+                _ => {
+                    let message = format!(
+                        "eruption_claims_rolled_back reopens eruption {} for {} which replay never saw claimed",
+                        claim.eruption_id, e.account_id
+                    );
+                    self.note(index, message);
+                }
+            }
+
+            self.fold_state_effect(&StateEffect::ClaimRolledBack {
+                account_id: e.account_id.clone(),
+                eruption_id: claim.eruption_id,
+                share: claim.share,
+                at: now,
+            });
+        }
+
+        if returned_to_pressure != e.returned_to_pressure {
+            let message = format!(
+                "eruption_claims_rolled_back for {} returned {} to pressure but replay computed {}",
+                e.account_id, e.returned_to_pressure, returned_to_pressure
+            );
+            self.note(index, message);
+        }
+
+        self.volcano_pressure += e.returned_to_pressure;
+    }
+
+    fn apply_exit(
+        &mut self,
+        index: usize,
+        event: &str,
+        account_id: &str,
+        gross: u128,
+        reported_remaining: u128,
+        consumed_lots: &[LotConsumption],
+    ) {
+        self.apply_consumed_lots(index, event, account_id, gross, consumed_lots);
+
+        let remaining = match self.participant_mut(account_id) {
+            Some(p) if p.position_balance >= gross => {
+                p.position_balance -= gross;
+                if p.position_balance == 0 {
+                    p.active = false;
+                }
+                Some(p.position_balance)
+            }
+            _ => None,
+        };
+
+        match remaining {
+            Some(value) if value != reported_remaining => {
+                let message = format!(
+                    "{} by {} left {} but event reports {}",
+                    event, account_id, value, reported_remaining
+                );
+                self.note(index, message);
+            }
+            Some(_) => {}
+            None => {
+                let message = format!(
+                    "{} of {} by {} exceeds replayed position",
+                    event, gross, account_id
+                );
+                self.note(index, message);
+            }
+        }
+    }
+
+    fn apply_consumed_lots(
+        &mut self,
+        index: usize,
+        event: &str,
+        account_id: &str,
+        gross: u128,
+        consumed_lots: &[LotConsumption],
+    ) {
+        let mut problems = Vec::new();
+
+        let consumed_total: u128 = consumed_lots.iter().map(|c| c.amount).sum();
+        if consumed_total != gross {
+            problems.push(format!(
+                "{} by {} consumed {} from lots but gross is {}",
+                event, account_id, consumed_total, gross
+            ));
+        }
+
+        if let Some(p) = self.participant_mut(account_id) {
+            for consumption in consumed_lots {
+                match p.lots.iter_mut().find(|l| l.lot_id == consumption.lot_id) {
+                    Some(lot) if lot.amount >= consumption.amount => {
+                        lot.amount -= consumption.amount
+                    }
+                    _ => problems.push(format!(
+                        "{} by {} consumed {} from lot {} beyond replayed amount",
+                        event, account_id, consumption.amount, consumption.lot_id
+                    )),
+                }
+            }
+
+            p.lots.retain(|lot| lot.amount > 0);
+        }
+
+        for message in problems {
+            self.note(index, message);
+        }
+    }
+
+    fn apply_snapshot(&mut self, index: usize, now: u64, e: &EruptionSnapshotEvent) {
+        self.eruption_count += 1;
+
+        if e.eruption_id != self.eruption_count {
+            let message = format!(
+                "eruption_snapshot id {} but replay expected {}",
+                e.eruption_id, self.eruption_count
+            );
+            self.note(index, message);
+        }
+
+        // Logs predating resumable scans carry no progress events; those
+        // snapshots were computed against the state at snapshot time.
+        let (eligible_accounts, eligible_positions) = match self.pending_eruption.take() {
+            Some(pending) if pending.phase_number == e.phase_number => {
+                if pending.cursor != pending.participant_count {
+                    let message = format!(
+                        "eruption {} finalized after scanning {} of {} participants",
+                        e.eruption_id, pending.cursor, pending.participant_count
+                    );
+                    self.note(index, message);
+                }
+                (pending.eligible_accounts, pending.eligible_positions)
+            }
+            Some(pending) => {
+                let message = format!(
+                    "eruption {} is phase {} but the pending scan is for phase {}",
+                    e.eruption_id, e.phase_number, pending.phase_number
+                );
+                self.note(index, message);
+                self.eligible_accounts(0..self.participants.len(), now, 0)
+            }
+            None => self.eligible_accounts(0..self.participants.len(), now, 0),
+        };
+
+        if eligible_accounts.len() as u64 != e.eligible_count {
+            let message = format!(
+                "eruption {} eligible_count {} but replay found {}",
+                e.eruption_id,
+                e.eligible_count,
+                eligible_accounts.len()
+            );
+            self.note(index, message);
+        }
+
+        let eligible_shares = if e.distribution_mode == POSITION_WEIGHTED {
+            let total: u128 = eligible_positions.iter().sum();
+            let pool = if e.phase_number == 1 {
+                e.threshold * 75 / 100
+            } else {
+                e.threshold * 60 / 100
+            };
+
+            eligible_positions
+                .iter()
+                .map(|position| mul_div(pool, *position, total))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        if eligible_shares != e.eligible_shares {
+            let message = format!(
+                "eruption {} eligible_shares {:?} but replay computed {:?}",
+                e.eruption_id, e.eligible_shares, eligible_shares
+            );
+            self.note(index, message);
+        }
+
+        let carried = self
+            .volcano_pressure
+            .checked_sub(e.threshold)
+            .map(|extra| extra + e.retained + e.unallocated_remainder);
+
+        match carried {
+            Some(value) if value == e.carried_pressure => {}
+            _ => {
+                let message = format!(
+                    "eruption {} carried pressure {} but replay computed {:?}",
+                    e.eruption_id, e.carried_pressure, carried
+                );
+                self.note(index, message);
+            }
+        }
+
+        self.volcano_pressure = e.carried_pressure;
+
+        if e.claim_window_ns > 0 && now.checked_add(e.claim_window_ns) != Some(e.claim_deadline) {
+            let message = format!(
+                "eruption {} claim deadline {} does not match claim window {}",
+                e.eruption_id, e.claim_deadline, e.claim_window_ns
+            );
+            self.note(index, message);
+        }
+
+        self.fold_state_effect(&StateEffect::EruptionSnapshot {
+            eruption_id: e.eruption_id,
+            phase_number: e.phase_number,
+            threshold: e.threshold,
+            distribution_pool: e.allocated_distribution,
+            retained_amount: e.retained,
+            unallocated_remainder: e.unallocated_remainder,
+            share_per_wallet: e.share_per_wallet,
+            carried_pressure: self.volcano_pressure,
+            claim_deadline: e.claim_deadline,
+            eligible_accounts: eligible_accounts.clone(),
+            at: now,
+        });
+
+        if e.distribution_mode == POSITION_WEIGHTED {
+            self.fold_state_effect(&StateEffect::EruptionShares {
+                eruption_id: e.eruption_id,
+                shares: eligible_shares.clone(),
+            });
+        }
+
+        self.eruptions.push(EruptionSnapshot {
+            eruption_id: e.eruption_id,
+            phase_number: e.phase_number,
+            threshold: e.threshold,
+            distribution_pool: e.allocated_distribution,
+            retained_amount: e.retained,
+            unallocated_remainder: e.unallocated_remainder,
+            share_per_wallet: e.share_per_wallet,
+            eligible_count: e.eligible_count,
+            claimed_count: 0,
+            created_at: now,
+            claim_deadline: e.claim_deadline,
+            eligible_accounts,
+            swept: false,
+            distribution_mode: e.distribution_mode.clone(),
+            eligible_shares,
+            claimed_amount: 0,
+            claim_window_ns: e.claim_window_ns,
+        });
+    }
+
+    fn apply_progress(&mut self, index: usize, e: &EruptionProgressEvent) {
+        if e.scanned_from == 0 {
+            if let Some(stale) = &self.pending_eruption {
+                let message = format!(
+                    "eruption scan for phase {} restarted phase {} scan",
+                    e.phase_number, stale.phase_number
+                );
+                self.note(index, message);
+            }
+
+            if e.participant_count != self.participants.len() as u64 {
+                let message = format!(
+                    "eruption scan for phase {} froze {} participants but replay has {}",
+                    e.phase_number,
+                    e.participant_count,
+                    self.participants.len()
+                );
+                self.note(index, message);
+            }
+
+            self.pending_eruption = Some(PendingEruption {
+                phase_number: e.phase_number,
+                as_of: e.as_of,
+                min_holding_age_ns: e.min_holding_age_ns,
+                participant_count: e.participant_count,
+                cursor: 0,
+                eligible_accounts: Vec::new(),
+                eligible_positions: Vec::new(),
+            });
+        }
+
+        let mut pending = match self.pending_eruption.take() {
+            Some(pending) if pending.phase_number == e.phase_number => pending,
+            other => {
+                self.pending_eruption = other;
+                let message = format!(
+                    "eruption_progress for phase {} without a matching scan",
+                    e.phase_number
+                );
+                self.note(index, message);
+                return;
+            }
+        };
+
+        if e.scanned_from != pending.cursor {
+            let message = format!(
+                "eruption scan for phase {} resumed at {} but replay cursor is {}",
+                e.phase_number, e.scanned_from, pending.cursor
+            );
+            self.note(index, message);
+        }
+
+        let from = (e.scanned_from as usize).min(self.participants.len());
+        let to = (e.scanned_to as usize).clamp(from, self.participants.len());
+        let (found, positions) =
+            self.eligible_accounts(from..to, pending.as_of, pending.min_holding_age_ns);
+
+        if found != e.eligible_accounts || positions != e.eligible_positions {
+            let message = format!(
+                "eruption scan for phase {} over [{}, {}) found {:?} but replay found {:?}",
+                e.phase_number, e.scanned_from, e.scanned_to, e.eligible_accounts, found
+            );
+            self.note(index, message);
+        }
+
+        pending.cursor = e.scanned_to;
+        pending.eligible_accounts.extend(found);
+        pending.eligible_positions.extend(positions);
+        self.pending_eruption = Some(pending);
+    }
+
+    fn apply_claim(&mut self, index: usize, now: u64, e: &EruptionClaimedEvent) {
+        match self.participant_mut(&e.account_id) {
+            Some(p) => {
+                p.claimed_eruption_ids.push(e.eruption_id);
+
+                // A compounded claim is credited by a deposit logged just before it.
+                if let Some(lot_id) = e.compounded_lot_id {
+                    if !p.lots.iter().any(|lot| lot.lot_id == lot_id) {
+                        let message = format!(
+                            "eruption {} compounded by {} into unknown lot {}",
+                            e.eruption_id, e.account_id, lot_id
+                        );
+                        self.note(index, message);
+                    }
+                }
+            }
+            None => {
+                let message = format!("eruption_claimed by unknown account {}", e.account_id);
+                self.note(index, message);
+            }
+        }
+
+        let snapshot = self
+            .eruptions
+            .iter_mut()
+            .find(|s| s.eruption_id == e.eruption_id);
+
+        let message = match snapshot {
+            Some(s) => {
+                let share = s.share_of(&e.account_id);
+                s.claimed_count += 1;
+                s.claimed_amount += e.share;
+
+                if s.claimed_count != e.claimed_count || share != e.share {
+                    Some(format!(
+                        "eruption {} claim by {} reports count {} share {} but replay has count {} share {}",
+                        e.eruption_id,
+                        e.account_id,
+                        e.claimed_count,
+                        e.share,
+                        s.claimed_count,
+                        share
+                    ))
+                } else {
+                    None
+                }
+            }
+            None => Some(format!(
+                "eruption_claimed for unknown eruption {}",
+                e.eruption_id
+            )),
+        };
+
+        if let Some(message) = message {
+            self.note(index, message);
+        }
+
+        self.fold_state_effect(&StateEffect::EruptionClaimed {
+            account_id: e.account_id.clone(),
+            eruption_id: e.eruption_id,
+            share: e.share,
+            at: now,
+        });
+    }
+
+    fn check_pressure(&mut self, index: usize, event: &str, reported: u128) {
+        if self.volcano_pressure != reported {
+            let message = format!(
+                "{} reports pressure {} but replay has {}",
+                event, reported, self.volcano_pressure
+            );
+            self.note(index, message);
+        }
+    }
+}
+
+pub fn parse_receipts(json: &str) -> Result<Vec<Receipt>, ReplayError> {
+    Ok(serde_json::from_str(json)?)
+}
+
+pub fn replay(receipts: &[Receipt]) -> Result<ReplayState, ReplayError> {
+    let mut state = ReplayState {
+        state_hash_head: to_hex(&[0; 32]),
+        ..ReplayState::default()
+    };
+
+    for (receipt_index, receipt) in receipts.iter().enumerate() {
+        let mut events = Vec::new();
+
+        for (log_index, log) in receipt.logs.iter().enumerate() {
+            match parse_event(log) {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => {}
+                Err(reason) => {
+                    return Err(ReplayError::MalformedEvent {
+                        receipt: receipt_index,
+                        log: log_index,
+                        reason,
+                    })
+                }
+            }
+        }
+
+        state.apply_receipt(receipt_index, receipt, &events);
+    }
+
+    Ok(state)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Difference {
+    pub path: String,
+    pub replayed: String,
+    pub dumped: String,
+}
+
+fn differ(out: &mut Vec<Difference>, path: String, replayed: String, dumped: String) {
+    if replayed != dumped {
+        out.push(Difference {
+            path,
+            replayed,
+            dumped,
+        });
+    }
+}
+
+pub fn diff_participants(replayed: &[Participant], dumped: &[Participant]) -> Vec<Difference> {
+    let mut out = Vec::new();
+
+    for r in replayed {
+        let path = format!("participants[{}]", r.account_id);
+
+        match dumped.iter().find(|d| d.account_id == r.account_id) {
+            None => differ(&mut out, path, "present".into(), "missing".into()),
+            Some(d) => {
+                let fields = [
+                    ("active", r.active.to_string(), d.active.to_string()),
+                    (
+                        "position_balance",
+                        r.position_balance.to_string(),
+                        d.position_balance.to_string(),
+                    ),
+                    (
+                        "total_deposited",
+                        r.total_deposited.to_string(),
+                        d.total_deposited.to_string(),
+                    ),
+                    (
+                        "created_at",
+                        r.created_at.to_string(),
+                        d.created_at.to_string(),
+                    ),
+                    (
+                        "lock_started_at",
+                        r.lock_started_at.to_string(),
+                        d.lock_started_at.to_string(),
+                    ),
+                    (
+                        "claimed_eruption_ids",
+                        format!("{:?}", r.claimed_eruption_ids),
+                        format!("{:?}", d.claimed_eruption_ids),
+                    ),
+                    ("lots", format!("{:?}", r.lots), format!("{:?}", d.lots)),
+                    (
+                        "next_lot_id",
+                        r.next_lot_id.to_string(),
+                        d.next_lot_id.to_string(),
+                    ),
+                    (
+                        "auto_compound",
+                        r.auto_compound.to_string(),
+                        d.auto_compound.to_string(),
+                    ),
+                    (
+                        "claim_agents",
+                        format!("{:?}", r.claim_agents),
+                        format!("{:?}", d.claim_agents),
+                    ),
+                    (
+                        "beneficiary_id",
+                        format!("{:?}", r.beneficiary_id),
+                        format!("{:?}", d.beneficiary_id),
+                    ),
+                    (
+                        "inactivity_period_ns",
+                        r.inactivity_period_ns.to_string(),
+                        d.inactivity_period_ns.to_string(),
+                    ),
+                ];
+
+                for (field, rv, dv) in fields {
+                    differ(&mut out, format!("{}.{}", path, field), rv, dv);
+                }
+            }
+        }
+    }
+
+    for d in dumped {
+        if !replayed.iter().any(|r| r.account_id == d.account_id) {
+            let path = format!("participants[{}]", d.account_id);
+            differ(&mut out, path, "missing".into(), "present".into());
+        }
+    }
+
+    let replayed_order: Vec<&str> = replayed.iter().map(|p| p.account_id.as_str()).collect();
+    let dumped_order: Vec<&str> = dumped.iter().map(|p| p.account_id.as_str()).collect();
+    differ(
+        &mut out,
+        "participants.order".into(),
+        format!("{:?}", replayed_order),
+        format!("{:?}", dumped_order),
+    );
+
+    out
+}
+
+pub fn diff_eruptions(
+    replayed: &[EruptionSnapshot],
+    dumped: &[EruptionSnapshot],
+) -> Vec<Difference> {
+    let mut out = Vec::new();
+
+    for r in replayed {
+        let path = format!("eruptions[{}]", r.eruption_id);
+
+        match dumped.iter().find(|d| d.eruption_id == r.eruption_id) {
+            None => differ(&mut out, path, "present".into(), "missing".into()),
+            Some(d) => {
+                let fields = [
+                    (
+                        "phase_number",
+                        r.phase_number.to_string(),
+                        d.phase_number.to_string(),
+                    ),
+                    (
+                        "threshold",
+                        r.threshold.to_string(),
+                        d.threshold.to_string(),
+                    ),
+                    (
+                        "distribution_pool",
+                        r.distribution_pool.to_string(),
+                        d.distribution_pool.to_string(),
+                    ),
+                    (
+                        "retained_amount",
+                        r.retained_amount.to_string(),
+                        d.retained_amount.to_string(),
+                    ),
+                    (
+                        "unallocated_remainder",
+                        r.unallocated_remainder.to_string(),
+                        d.unallocated_remainder.to_string(),
+                    ),
+                    (
+                        "share_per_wallet",
+                        r.share_per_wallet.to_string(),
+                        d.share_per_wallet.to_string(),
+                    ),
+                    (
+                        "eligible_count",
+                        r.eligible_count.to_string(),
+                        d.eligible_count.to_string(),
+                    ),
+                    (
+                        "claimed_count",
+                        r.claimed_count.to_string(),
+                        d.claimed_count.to_string(),
+                    ),
+                    (
+                        "created_at",
+                        r.created_at.to_string(),
+                        d.created_at.to_string(),
+                    ),
+                    (
+                        "claim_deadline",
+                        r.claim_deadline.to_string(),
+                        d.claim_deadline.to_string(),
+                    ),
+                    (
+                        "eligible_accounts",
+                        format!("{:?}", r.eligible_accounts),
+                        format!("{:?}", d.eligible_accounts),
+                    ),
+                    ("swept", r.swept.to_string(), d.swept.to_string()),
+                    (
+                        "distribution_mode",
+                        r.distribution_mode.clone(),
+                        d.distribution_mode.clone(),
+                    ),
+                    (
+                        "eligible_shares",
+                        format!("{:?}", r.eligible_shares),
+                        format!("{:?}", d.eligible_shares),
+                    ),
+                    (
+                        "claimed_amount",
+                        r.claimed_amount.to_string(),
+                        d.claimed_amount.to_string(),
+                    ),
+                    (
+                        "claim_window_ns",
+                        r.claim_window_ns.to_string(),
+                        d.claim_window_ns.to_string(),
+                    ),
+                ];
+
+                for (field, rv, dv) in fields {
+                    differ(&mut out, format!("{}.{}", path, field), rv, dv);
+                }
+            }
+        }
+    }
+
+    for d in dumped {
+        if !replayed.iter().any(|r| r.eruption_id == d.eruption_id) {
+            let path = format!("eruptions[{}]", d.eruption_id);
+            differ(&mut out, path, "missing".into(), "present".into());
+        }
+    }
+
+    out
+}
+
+pub fn diff_state_hash(replayed: &ReplayState, head: &str, length: Option<u64>) -> Vec<Difference> {
+    let mut out = Vec::new();
+
+    differ(
+        &mut out,
+        "state_hash_chain.head".into(),
+        replayed.state_hash_head.clone(),
+        head.to_lowercase(),
+    );
+
+    if let Some(length) = length {
+        differ(
+            &mut out,
+            "state_hash_chain.length".into(),
+            replayed.state_hash_length.to_string(),
+            length.to_string(),
+        );
+    }
+
+    out
+}
+
+// Mirrors the contract's 256-bit floor(a * b / denominator).
+fn mul_div(a: u128, b: u128, denominator: u128) -> u128 {
+    const MASK: u128 = u64::MAX as u128;
+
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+
+    let ll = a0 * b0;
+    let lh = a0 * b1;
+    let hl = a1 * b0;
+    let hh = a1 * b1;
+
+    let mid = (ll >> 64) + (lh & MASK) + (hl & MASK);
+    let lo = (ll & MASK) | ((mid & MASK) << 64);
+    let hi = hh + (lh >> 64) + (hl >> 64) + (mid >> 64);
+
+    let mut remainder: u128 = 0;
+    let mut quotient: u128 = 0;
+
+    for bit in (0..256).rev() {
+        let next = if bit >= 128 {
+            (hi >> (bit - 128)) & 1
+        } else {
+            (lo >> bit) & 1
+        };
+
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | next;
+        quotient <<= 1;
+
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+
+    quotient
+}
+
+fn equal_share() -> String {
+    EQUAL_SHARE.to_string()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn u128_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+fn u128_string_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u128>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| value.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn u64_flex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flex {
+        Number(u64),
+        Text(String),
+    }
+
+    match Flex::deserialize(deserializer)? {
+        Flex::Number(value) => Ok(value),
+        Flex::Text(value) => value.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+    const MINUTE_NS: u64 = 60_000_000_000;
+    const T0: u64 = 1_000_000_000_000;
+
+    // bob's 10 NEAR deposit: 9.5 NEAR locked, 0.105 NEAR to pressure.
+    const LOCKED: u128 = 9_500_000_000_000_000_000_000_000;
+    const VOLCANO: u128 = 105_000_000_000_000_000_000_000;
+
+    const ERUPTED_AT: u64 = T0 + 15 * MINUTE_NS;
+    const CLAIM_WINDOW_NS: u64 = 30 * MINUTE_NS;
+    const POOL: u128 = VOLCANO * 75 / 100;
+
+    fn event(name: &str, data: Value) -> String {
+        let envelope = json!({
+            "standard": EVENT_STANDARD,
+            "version": "1.1.0",
+            "event": name,
+            "data": data,
+        });
+        format!("{}{}", EVENT_LOG_PREFIX, envelope)
+    }
+
+    fn receipt(block_timestamp: u64, logs: Vec<String>) -> Receipt {
+        Receipt {
+            receipt_id: None,
+            block_timestamp,
+            logs,
+        }
+    }
+
+    fn replayed(receipts: &[Receipt]) -> ReplayState {
+        let state = replay(receipts).unwrap();
+        assert_eq!(state.inconsistencies, Vec::<String>::new());
+        state
+    }
+
+    fn bob_deposit() -> Receipt {
+        let log = event(
+            "deposit",
+            json!({
+                "account_id": "bob",
+                "locked_position": LOCKED.to_string(),
+                "volcano": VOLCANO.to_string(),
+                "pressure": VOLCANO.to_string(),
+                "lot_id": 1,
+            }),
+        );
+        receipt(T0, vec![log])
+    }
+
+    fn eruption_progress() -> String {
+        event(
+            "eruption_progress",
+            json!({
+                "phase_number": 1,
+                "as_of": ERUPTED_AT,
+                "participant_count": 1,
+                "scanned_from": 0,
+                "scanned_to": 1,
+                "eligible_accounts": ["bob"],
+                "eligible_positions": [LOCKED.to_string()],
+                "min_holding_age_ns": 10 * MINUTE_NS,
+            }),
+        )
+    }
+
+    // Phase 1 fires at exactly bob's deposit pressure: bob is the only
+    // eligible wallet and the retained 25% carries forward.
+    fn bob_eruption() -> Receipt {
+        let snapshot = event(
+            "eruption_snapshot",
+            json!({
+                "eruption_id": 1,
+                "phase_number": 1,
+                "threshold": VOLCANO.to_string(),
+                "allocated_distribution": POOL.to_string(),
+                "retained": (VOLCANO - POOL).to_string(),
+                "unallocated_remainder": "0",
+                "share_per_wallet": POOL.to_string(),
+                "eligible_count": 1,
+                "carried_pressure": (VOLCANO - POOL).to_string(),
+                "claim_deadline": ERUPTED_AT + CLAIM_WINDOW_NS,
+                "distribution_mode": EQUAL_SHARE,
+                "claim_window_ns": CLAIM_WINDOW_NS,
+            }),
+        );
+        receipt(ERUPTED_AT, vec![eruption_progress(), snapshot])
+    }
+
+    fn bob_claim() -> Receipt {
+        let log = event(
+            "eruption_claimed",
+            json!({
+                "account_id": "bob",
+                "eruption_id": 1,
+                "share": POOL.to_string(),
+                "claimed_count": 1,
+            }),
+        );
+        receipt(ERUPTED_AT + MINUTE_NS, vec![log])
+    }
+
+    #[test]
+    fn deposit_opens_participant_and_lot() {
+        let state = replayed(&[bob_deposit()]);
+
+        let bob = &state.participants[0];
+        assert!(bob.active);
+        assert_eq!(bob.position_balance, LOCKED);
+        assert_eq!(bob.created_at, T0);
+        assert_eq!(bob.lots.len(), 1);
+        assert_eq!((bob.lots[0].lot_id, bob.lots[0].amount), (1, LOCKED));
+        assert_eq!(bob.next_lot_id, 2);
+        assert_eq!(state.volcano_pressure, VOLCANO);
+        assert_eq!(state.state_hash_length, 1);
+    }
+
+    #[test]
+    fn record_created_adds_pressure() {
+        let log = event(
+            "record_created",
+            json!({
+                "creator": "bob",
+                "pressure_added": "16",
+                "record_number": 1,
+                "pressure": (VOLCANO + 16).to_string(),
+            }),
+        );
+        let state = replayed(&[bob_deposit(), receipt(T0 + MINUTE_NS, vec![log])]);
+
+        assert_eq!(state.created_record_count, 1);
+        assert_eq!(state.volcano_pressure, VOLCANO + 16);
+        assert_eq!(state.state_hash_length, 2);
+    }
+
+    #[test]
+    fn withdraw_position_consumes_lots() {
+        let log = event(
+            "withdraw_position",
+            json!({
+                "account_id": "bob",
+                "gross": NEAR.to_string(),
+                "volcano": "7",
+                "remaining_position": (LOCKED - NEAR).to_string(),
+                "pressure": (VOLCANO + 7).to_string(),
+                "consumed_lots": [{"lot_id": 1, "amount": NEAR.to_string()}],
+            }),
+        );
+        let state = replayed(&[bob_deposit(), receipt(T0 + MINUTE_NS, vec![log])]);
+
+        let bob = &state.participants[0];
+        assert_eq!(bob.position_balance, LOCKED - NEAR);
+        assert_eq!(bob.lots[0].amount, LOCKED - NEAR);
+        assert_eq!(bob.lots[0].original_amount, LOCKED);
+        assert_eq!(state.volcano_pressure, VOLCANO + 7);
+    }
+
+    #[test]
+    fn early_exit_adds_penalty_and_deactivates_emptied_position() {
+        let log = event(
+            "early_exit",
+            json!({
+                "account_id": "bob",
+                "gross": LOCKED.to_string(),
+                "volcano": "7",
+                "penalty": "100",
+                "remaining_position": "0",
+                "pressure": (VOLCANO + 107).to_string(),
+                "consumed_lots": [{"lot_id": 1, "amount": LOCKED.to_string()}],
+            }),
+        );
+        let state = replayed(&[bob_deposit(), receipt(T0 + MINUTE_NS, vec![log])]);
+
+        let bob = &state.participants[0];
+        assert!(!bob.active);
+        assert!(bob.lots.is_empty());
+        assert_eq!(state.volcano_pressure, VOLCANO + 107);
+    }
+
+    #[test]
+    fn eruption_progress_opens_a_pending_scan() {
+        let state = replayed(&[
+            bob_deposit(),
+            receipt(ERUPTED_AT, vec![eruption_progress()]),
+        ]);
+
+        let pending = state.pending_eruption.unwrap();
+        assert_eq!(pending.phase_number, 1);
+        assert_eq!(pending.cursor, 1);
+        assert_eq!(pending.eligible_accounts, vec!["bob".to_string()]);
+        assert_eq!(pending.eligible_positions, vec![LOCKED]);
+    }
+
+    #[test]
+    fn eruption_snapshot_finalizes_the_scan() {
+        let state = replayed(&[bob_deposit(), bob_eruption()]);
+
+        assert!(state.pending_eruption.is_none());
+        assert_eq!(state.eruption_count, 1);
+        assert_eq!(state.volcano_pressure, VOLCANO - POOL);
+
+        let snapshot = &state.eruptions[0];
+        assert_eq!(snapshot.eligible_accounts, vec!["bob".to_string()]);
+        assert_eq!(snapshot.share_of("bob"), POOL);
+        assert_eq!(snapshot.claim_deadline, ERUPTED_AT + CLAIM_WINDOW_NS);
+    }
+
+    #[test]
+    fn eruption_claimed_marks_participant_and_snapshot() {
+        let state = replayed(&[bob_deposit(), bob_eruption(), bob_claim()]);
+
+        assert_eq!(state.participants[0].claimed_eruption_ids, vec![1]);
+        assert_eq!(state.eruptions[0].claimed_count, 1);
+        assert_eq!(state.eruptions[0].claimed_amount, POOL);
+    }
+
+    #[test]
+    fn eruption_claims_swept_returns_unclaimed_pool() {
+        let log = event(
+            "eruption_claims_swept",
+            json!({
+                "eruption_id": 1,
+                "unclaimed_count": 1,
+                "amount": POOL.to_string(),
+                "pressure": VOLCANO.to_string(),
+            }),
+        );
+        let swept_at = ERUPTED_AT + CLAIM_WINDOW_NS + MINUTE_NS;
+        let state = replayed(&[bob_deposit(), bob_eruption(), receipt(swept_at, vec![log])]);
+
+        assert!(state.eruptions[0].swept);
+        assert_eq!(state.volcano_pressure, VOLCANO);
+    }
+
+    #[test]
+    fn eruption_claims_rolled_back_reopens_the_claim() {
+        let log = event(
+            "eruption_claims_rolled_back",
+            json!({
+                "account_id": "bob",
+                "claims": [{"eruption_id": 1, "share": POOL.to_string()}],
+                "total": POOL.to_string(),
+                "returned_to_pressure": "0",
+                "pressure": (VOLCANO - POOL).to_string(),
+            }),
+        );
+        let rolled_back_at = ERUPTED_AT + 2 * MINUTE_NS;
+        let state = replayed(&[
+            bob_deposit(),
+            bob_eruption(),
+            bob_claim(),
+            receipt(rolled_back_at, vec![log]),
+        ]);
+
+        assert!(state.participants[0].claimed_eruption_ids.is_empty());
+        assert_eq!(state.eruptions[0].claimed_count, 0);
+        assert_eq!(state.eruptions[0].claimed_amount, 0);
+    }
+
+    #[test]
+    fn eligibility_policy_updated_replaces_the_policy() {
+        let log = event(
+            "eligibility_policy_updated",
+            json!({
+                "min_account_age_ns": MINUTE_NS,
+                "attester_public_key": "ed25519:key",
+                "attestation_required": true,
+            }),
+        );
+        let state = replayed(&[receipt(T0, vec![log])]);
+
+        assert_eq!(state.eligibility_policy.min_account_age_ns, MINUTE_NS);
+        assert!(state.eligibility_policy.attestation_required);
+    }
+
+    #[test]
+    fn eligibility_attested_keeps_the_latest_attestation() {
+        let attest = |expires_at: u64| {
+            event(
+                "eligibility_attested",
+                json!({
+                    "account_id": "bob",
+                    "attester_public_key": "ed25519:key",
+                    "expires_at": expires_at,
+                    "submitted_at": T0,
+                }),
+            )
+        };
+        let state = replayed(&[receipt(T0, vec![attest(T0 + 1), attest(T0 + 2)])]);
+
+        assert_eq!(state.eligibility_attestations.len(), 1);
+        assert_eq!(state.eligibility_attestations[0].expires_at, T0 + 2);
+    }
+
+    #[test]
+    fn auto_compound_updated_sets_the_preference() {
+        let log = event(
+            "auto_compound_updated",
+            json!({"account_id": "bob", "enabled": true}),
+        );
+        let state = replayed(&[bob_deposit(), receipt(T0 + MINUTE_NS, vec![log])]);
+
+        assert!(state.participants[0].auto_compound);
+    }
+
+    #[test]
+    fn claim_agent_updated_adds_and_removes_agents() {
+        let update = |authorized: bool| {
+            event(
+                "claim_agent_updated",
+                json!({"account_id": "bob", "agent_id": "keeper", "authorized": authorized}),
+            )
+        };
+        let added = replayed(&[bob_deposit(), receipt(T0, vec![update(true)])]);
+        assert_eq!(
+            added.participants[0].claim_agents,
+            vec!["keeper".to_string()]
+        );
+
+        let removed = replayed(&[
+            bob_deposit(),
+            receipt(T0, vec![update(true), update(false)]),
+        ]);
+        assert!(removed.participants[0].claim_agents.is_empty());
+    }
+
+    #[test]
+    fn beneficiary_updated_records_the_designation() {
+        let log = event(
+            "beneficiary_updated",
+            json!({
+                "account_id": "bob",
+                "beneficiary_id": "carol",
+                "inactivity_period_ns": 30 * MINUTE_NS,
+            }),
+        );
+        let state = replayed(&[bob_deposit(), receipt(T0, vec![log])]);
+
+        let bob = &state.participants[0];
+        assert_eq!(bob.beneficiary_id.as_deref(), Some("carol"));
+        assert_eq!(bob.inactivity_period_ns, 30 * MINUTE_NS);
+    }
+
+    #[test]
+    fn other_events_and_legacy_lines_leave_state_alone() {
+        let logs = vec![
+            event("pause_flags_updated", json!({"deposits": true})),
+            "DEPOSIT caller=bob amount=1".to_string(),
+        ];
+        let state = replayed(&[receipt(T0, logs)]);
+
+        assert_eq!(state.events_applied, 1);
+        assert_eq!(state.state_hash_length, 0);
+        assert_eq!(state.state_hash_head, "0".repeat(64));
+    }
+
+    #[test]
+    fn unsupported_major_version_is_rejected() {
+        let log = event("deposit", json!({})).replace("\"1.1.0\"", "\"2.0.0\"");
+        let error = replay(&[receipt(T0, vec![log])]).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("unsupported deposit version 2.0.0"));
+    }
+
+    #[test]
+    fn pressure_mismatch_is_reported_not_fatal() {
+        let log = event(
+            "deposit",
+            json!({
+                "account_id": "bob",
+                "locked_position": LOCKED.to_string(),
+                "volcano": VOLCANO.to_string(),
+                "pressure": "1",
+                "lot_id": 1,
+            }),
+        );
+        let state = replay(&[receipt(T0, vec![log])]).unwrap();
+
+        assert_eq!(state.inconsistencies.len(), 1);
+        assert!(state.inconsistencies[0].contains("deposit reports pressure 1"));
+    }
+
+    // Logs captured from the contract test
+    // `state_hash_chain_head_for_the_indexer_fixture`, which pins the same head.
+    const CONTRACT_RECEIPTS: &str = r#"[
+  {
+    "block_timestamp": 1000000000000,
+    "logs": [
+      "EVENT_JSON:{\"standard\":\"near_intersect_volcano\",\"version\":\"1.1.0\",\"event\":\"deposit\",\"data\":{\"account_id\":\"bob\",\"amount\":\"10000000000000000000000000\",\"fee\":\"500000000000000000000000\",\"locked_position\":\"9500000000000000000000000\",\"treasury\":\"140000000000000000000000\",\"growth\":\"90000000000000000000000\",\"volcano\":\"105000000000000000000000\",\"reserve\":\"55000000000000000000000\",\"core_ops\":\"110000000000000000000000\",\"pressure\":\"105000000000000000000000\",\"participant_count\":1,\"lot_id\":1}}",
+      "DEPOSIT caller=bob amount=10000000000000000000000000 fee=500000000000000000000000 locked_position=9500000000000000000000000 treasury=140000000000000000000000 growth=90000000000000000000000 volcano=105000000000000000000000 reserve=55000000000000000000000 core_ops=110000000000000000000000 pressure=105000000000000000000000 participants=1"
+    ]
+  },
+  {
+    "block_timestamp": 1060000000000,
+    "logs": [
+      "EVENT_JSON:{\"standard\":\"near_intersect_volcano\",\"version\":\"1.0.0\",\"event\":\"record_created\",\"data\":{\"creator\":\"bob\",\"fee\":\"32000000000000000000000\",\"treasury\":\"16000000000000000000000\",\"pressure_added\":\"16000000000000000000000\",\"record_number\":1,\"pressure\":\"121000000000000000000000\"}}",
+      "VOLCANO_RECORD_CREATED creator=bob fee=32000000000000000000000 treasury=16000000000000000000000 pressure_added=16000000000000000000000 record_number=1 pressure=121000000000000000000000 note=not_nep141_token_creation"
+    ]
+  },
+  {
+    "block_timestamp": 8260000000000,
+    "logs": [
+      "EVENT_JSON:{\"standard\":\"near_intersect_volcano\",\"version\":\"1.1.0\",\"event\":\"withdraw_position\",\"data\":{\"account_id\":\"bob\",\"gross\":\"1000000000000000000000000\",\"net_to_user\":\"950000000000000000000000\",\"fee\":\"50000000000000000000000\",\"treasury\":\"14000000000000000000000\",\"growth\":\"9000000000000000000000\",\"volcano\":\"10500000000000000000000\",\"reserve\":\"5500000000000000000000\",\"core_ops\":\"11000000000000000000000\",\"remaining_position\":\"8500000000000000000000000\",\"pressure\":\"131500000000000000000000\",\"consumed_lots\":[{\"lot_id\":1,\"amount\":\"1000000000000000000000000\"}]}}",
+      "WITHDRAW_POSITION caller=bob gross=1000000000000000000000000 net_to_user=950000000000000000000000 fee=50000000000000000000000 treasury=14000000000000000000000 growth=9000000000000000000000 volcano=10500000000000000000000 reserve=5500000000000000000000 core_ops=11000000000000000000000 remaining_position=8500000000000000000000000 pressure=131500000000000000000000"
+    ]
+  }
+]"#;
+
+    #[test]
+    fn replayed_hash_chain_matches_the_contract() {
+        let receipts = parse_receipts(CONTRACT_RECEIPTS).unwrap();
+        let state = replayed(&receipts);
+
+        assert!(diff_state_hash(
+            &state,
+            "2965eaa0676ac384bb37b37dee21c77c74ce07116381fe76314b78faa53eb252",
+            Some(3),
+        )
+        .is_empty());
+        assert_eq!(
+            state.participants[0].position_balance,
+            8_500_000_000_000_000_000_000_000
+        );
+    }
+
+    #[test]
+    fn diff_reports_mismatched_fields() {
+        let state = replayed(&[bob_deposit()]);
+
+        let mut dumped = state.participants.clone();
+        dumped[0].position_balance += 1;
+        dumped[0].auto_compound = true;
+
+        let differences = diff_participants(&state.participants, &dumped);
+        let paths: Vec<&str> = differences.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "participants[bob].position_balance",
+                "participants[bob].auto_compound",
+            ]
+        );
+        assert_eq!(differences[0].replayed, LOCKED.to_string());
+        assert_eq!(differences[0].dumped, (LOCKED + 1).to_string());
+
+        let stale = diff_state_hash(&state, &"0".repeat(64), Some(0));
+        assert_eq!(stale.len(), 2);
+        assert!(diff_participants(&state.participants, &state.participants).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use near_intersect_indexer::{
+    diff_eruptions, diff_participants, diff_state_hash, parse_receipts, replay, Difference,
+    EruptionSnapshot, Participant, ReplayState,
+};
+use serde::Serialize;
+use std::io::Read;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: near-intersect-replay <receipts.json|-> [--participants <dump.json>] [--eruptions <dump.json>] [--state-hash <head> [--state-hash-length <n>]]";
+
+#[derive(Serialize)]
+struct Report {
+    state: ReplayState,
+    differences: Vec<Difference>,
+}
+
+struct Args {
+    receipts: String,
+    participants: Option<String>,
+    eruptions: Option<String>,
+    state_hash: Option<String>,
+    state_hash_length: Option<u64>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut receipts = None;
+    let mut participants = None;
+    let mut eruptions = None;
+    let mut state_hash = None;
+    let mut state_hash_length = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--participants" => participants = Some(args.next().ok_or(USAGE)?),
+            "--eruptions" => eruptions = Some(args.next().ok_or(USAGE)?),
+            "--state-hash" => state_hash = Some(args.next().ok_or(USAGE)?),
+            "--state-hash-length" => {
+                let value = args.next().ok_or(USAGE)?;
+                state_hash_length = Some(value.parse().map_err(|_| USAGE.to_string())?);
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if receipts.is_none() => receipts = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    Ok(Args {
+        receipts: receipts.ok_or(USAGE)?,
+        participants,
+        eruptions,
+        state_hash,
+        state_hash_length,
+    })
+}
+
+fn read_input(path: &str) -> Result<String, String> {
+    if path == "-" {
+        let mut buffer = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buffer)
+            .map_err(|e| format!("stdin: {}", e))?;
+        return Ok(buffer);
+    }
+
+    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn run() -> Result<bool, String> {
+    let args = parse_args()?;
+
+    let receipts = parse_receipts(&read_input(&args.receipts)?).map_err(|e| e.to_string())?;
+    let state = replay(&receipts).map_err(|e| e.to_string())?;
+
+    let mut differences = Vec::new();
+
+    if let Some(path) = &args.participants {
+        let dumped: Vec<Participant> =
+            serde_json::from_str(&read_input(path)?).map_err(|e| format!("{}: {}", path, e))?;
+        differences.extend(diff_participants(&state.participants, &dumped));
+    }
+
+    if let Some(path) = &args.eruptions {
+        let dumped: Vec<EruptionSnapshot> =
+            serde_json::from_str(&read_input(path)?).map_err(|e| format!("{}: {}", path, e))?;
+        differences.extend(diff_eruptions(&state.eruptions, &dumped));
+    }
+
+    if let Some(head) = &args.state_hash {
+        differences.extend(diff_state_hash(&state, head, args.state_hash_length));
+    }
+
+    let clean = state.inconsistencies.is_empty() && differences.is_empty();
+    let report = Report { state, differences };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+    );
+
+    Ok(clean)
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}